@@ -0,0 +1,67 @@
+use text_engine::Vec2;
+
+// Terminal cells are about twice as tall as they are wide, so distances
+// across are halved to keep the shockwave round on screen
+const ASPECT: f64 = 0.5;
+
+/// The ring spreading out from where a bomb went off
+///
+/// The ring damages enemies and clears enemy bullets as it reaches them, so
+/// whatever is closest to the player is dealt with first.
+#[derive(Clone)]
+pub struct Shockwave {
+    pub centre: Vec2,
+    pub radius: f64, // Rows from the centre the ring has reached
+}
+impl Shockwave {
+    /// Returns a shockwave just starting out
+    ///
+    /// # Arguments
+    /// * centre: where the bomb went off
+    pub fn new(centre: Vec2) -> Self {
+        Self {
+            centre,
+            radius: 0.0,
+        }
+    }
+
+    /// Returns how far a point is from the centre, in rows
+    ///
+    /// # Arguments
+    /// * pos: the point
+    pub fn distance(&self, pos: Vec2) -> f64 {
+        let offset = pos - self.centre;
+        (offset.x * ASPECT).hypot(offset.y)
+    }
+
+    /// Returns true if the ring has grown past every cell of a map
+    ///
+    /// # Arguments
+    /// * (width, height): the map's size
+    pub fn is_done(&self, (width, height): (usize, usize)) -> bool {
+        let corners = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(width as f64, 0.0),
+            Vec2::new(0.0, height as f64),
+            Vec2::new(width as f64, height as f64),
+        ];
+        corners.into_iter().all(|c| self.distance(c) < self.radius)
+    }
+
+    /// Returns the cells inside the walls the ring is passing through
+    ///
+    /// # Arguments
+    /// * (width, height): the map's size
+    pub fn ring(&self, (width, height): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let pos = Vec2::new(x as f64, y as f64);
+                if (self.distance(pos) - self.radius).abs() < 0.5 {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+}
@@ -0,0 +1,386 @@
+use text_engine::data::{DataError, Section};
+use text_engine::pattern::Library;
+use text_engine::{Enemy, Sprite, Vec2};
+
+use crate::enemy::EnemyWeapon;
+use crate::movement::{self, Movement};
+use crate::{HEIGHT, WIDTH};
+
+// Name of the part that destroys the boss when it is destroyed
+const CORE: &str = "core";
+
+/// One destructible piece of a boss, e.g. a turret
+pub struct PartDef {
+    pub name: String,
+    pub pos: (usize, usize),  // Upper-left corner within the boss's sprite
+    pub size: (usize, usize), // Width and height
+    pub health: usize,        // Hits it takes to destroy
+}
+impl PartDef {
+    /// Returns true if the part covers a cell of the boss's sprite
+    ///
+    /// # Arguments
+    /// * (x, y): the cell, relative to the upper-left corner of the sprite
+    fn covers(&self, (x, y): (usize, usize)) -> bool {
+        (self.pos.0..self.pos.0 + self.size.0).contains(&x)
+            && (self.pos.1..self.pos.1 + self.size.1).contains(&y)
+    }
+}
+
+/// One stage of a boss fight, with its own movement and weapon
+pub struct Phase {
+    pub at: usize,   // Starts once the boss's health is at or below this percent
+    pub speed: u128, // Milliseconds between moves
+    pub movement: Box<dyn Movement>,
+    pub weapon: Option<EnemyWeapon>, // Fired from every part still standing
+}
+
+/// A type of boss, as described in a data file
+pub struct BossDef {
+    pub name: String,
+    pub sprite: Sprite,
+    pub parts: Vec<PartDef>,
+    pub phases: Vec<Phase>, // In order, from full health down
+    pub score: i32,         // Points for destroying its core
+}
+impl BossDef {
+    /// Reads a boss from its section of a data file
+    ///
+    /// # Arguments
+    /// * name: the name of the boss, without the `boss` prefix
+    /// * section: the section describing the boss
+    /// * patterns: the patterns its weapons can use
+    pub fn read(name: &str, section: &Section, patterns: &Library) -> Result<Self, DataError> {
+        section.check_keys(&["sprite", "part", "phase", "score"])?;
+
+        let rows = section.get_all("sprite");
+        let sprite = Sprite::new(&rows);
+        let (width, height) = sprite.size();
+        if width == 0 || width > WIDTH - 2 || height > HEIGHT / 2 - 1 {
+            return Err(section.error(
+                section.line,
+                &format!(
+                    "[{}] needs a sprite that fits the top half of the map",
+                    section.name
+                ),
+            ));
+        }
+
+        let mut parts = Vec::new();
+        let mut phases: Vec<Phase> = Vec::new();
+        for field in &section.fields {
+            match field.key.as_str() {
+                "part" => {
+                    let part = read_part(&field.value, (width, height))
+                        .map_err(|e| section.error(field.line, &e))?;
+                    parts.push(part);
+                }
+                "phase" => {
+                    let phase = read_phase(&field.value, patterns)
+                        .map_err(|e| section.error(field.line, &e))?;
+                    let out_of_order = match phases.last() {
+                        Some(last) => phase.at >= last.at,
+                        None => phase.at != 100,
+                    };
+                    if out_of_order {
+                        return Err(section
+                            .error(field.line, "phases must start at 100 and go down in order"));
+                    }
+                    phases.push(phase);
+                }
+                _ => (),
+            }
+        }
+        if !parts.iter().any(|p| p.name == CORE) || phases.is_empty() {
+            return Err(section.error(
+                section.line,
+                &format!(
+                    "[{}] needs a core part and at least one phase",
+                    section.name
+                ),
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            sprite,
+            parts,
+            phases,
+            score: section.parse_or("score", 0)?,
+        })
+    }
+
+    /// Returns the total health of all the boss's parts
+    pub fn health(&self) -> usize {
+        self.parts.iter().map(|p| p.health).sum()
+    }
+}
+
+/// Reads a part from its description in a data file
+///
+/// The description is the part's name, the column and row of its upper-left
+/// corner within the sprite, its width and height, then its health, e.g.
+/// `core 4 1 3 2 20`.
+///
+/// # Arguments
+/// * text: the description
+/// * sprite: the width and height of the boss's sprite
+fn read_part(text: &str, sprite: (usize, usize)) -> Result<PartDef, String> {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    let name = parts.first().copied().unwrap_or("");
+    let nums = parts[1.min(parts.len())..]
+        .iter()
+        .map(|p| p.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>();
+    match nums {
+        Ok(n) if n.len() == 5 && n[2] > 0 && n[3] > 0 && n[4] > 0 => {
+            if n[0] + n[2] > sprite.0 || n[1] + n[3] > sprite.1 {
+                return Err(format!("part {} does not fit in the sprite", name));
+            }
+            Ok(PartDef {
+                name: name.to_string(),
+                pos: (n[0], n[1]),
+                size: (n[2], n[3]),
+                health: n[4],
+            })
+        }
+        _ => Err(format!("invalid part {}", text)),
+    }
+}
+
+/// Reads a phase from its description in a data file
+///
+/// The description is the health percent the phase starts at, the
+/// milliseconds between moves, the movement and the weapon, separated by `|`,
+/// e.g. `50 | 300 | strafe 10 0 | spiral 400`.
+///
+/// # Arguments
+/// * text: the description
+/// * patterns: the patterns the weapon can use
+fn read_phase(text: &str, patterns: &Library) -> Result<Phase, String> {
+    let parts: Vec<&str> = text.split('|').map(|p| p.trim()).collect();
+    if parts.len() != 4 {
+        return Err(format!("invalid phase {}", text));
+    }
+    let at = parts[0]
+        .parse()
+        .map_err(|_| format!("invalid health percent {}", parts[0]))?;
+    let speed = match parts[1].parse() {
+        Ok(speed) if speed > 0 => speed,
+        _ => return Err(format!("invalid speed {}", parts[1])),
+    };
+    Ok(Phase {
+        at,
+        speed,
+        movement: movement::parse(parts[2])?,
+        weapon: EnemyWeapon::parse(parts[3], patterns)?,
+    })
+}
+
+/// A boss in play
+#[derive(Clone)]
+pub struct Boss {
+    pub body: Enemy, // Position, movement and firing state, shared with regular enemies
+    pub def: usize,  // Index of the boss's type in the game's registry
+    pub parts: Vec<usize>, // Health left in each part, in the order of its type's parts
+    pub phase: usize,
+}
+impl Boss {
+    /// Returns a new boss, just above the top of the map
+    ///
+    /// # Arguments
+    /// * index: the index of the boss's type
+    /// * def: the boss's type
+    pub fn new(index: usize, def: &BossDef) -> Self {
+        let (width, height) = def.sprite.size();
+        let pos = Vec2::new(((WIDTH - width) / 2) as f64, -(height as f64));
+        Self {
+            body: Enemy::new(pos, (width, height), 0, def.health()),
+            def: index,
+            parts: def.parts.iter().map(|p| p.health).collect(),
+            phase: 0,
+        }
+    }
+
+    /// Returns true once the boss has moved fully onto the map
+    pub fn arrived(&self) -> bool {
+        self.body.pos.y >= 1.0
+    }
+
+    /// Returns the index of the part covering a cell, if it is still standing
+    ///
+    /// # Arguments
+    /// * def: the boss's type
+    /// * cell: the cell to check
+    pub fn part_at(&self, def: &BossDef, cell: (i32, i32)) -> Option<usize> {
+        let local = self.local(cell)?;
+        def.parts
+            .iter()
+            .enumerate()
+            .position(|(i, p)| self.parts[i] > 0 && p.covers(local))
+    }
+
+    /// Returns true if a visible part of the boss covers a cell
+    ///
+    /// # Arguments
+    /// * def: the boss's type
+    /// * cell: the cell to check
+    pub fn covers(&self, def: &BossDef, cell: (i32, i32)) -> bool {
+        match self.local(cell) {
+            Some((x, y)) => self.sprite(def).rows[y][x] != ' ',
+            None => false,
+        }
+    }
+
+    /// Returns the boss's sprite, with destroyed parts taken out
+    ///
+    /// # Arguments
+    /// * def: the boss's type
+    pub fn sprite(&self, def: &BossDef) -> Sprite {
+        let mut sprite = def.sprite.clone();
+        for (y, row) in sprite.rows.iter_mut().enumerate() {
+            for (x, c) in row.iter_mut().enumerate() {
+                let destroyed = def
+                    .parts
+                    .iter()
+                    .enumerate()
+                    .any(|(i, p)| self.parts[i] == 0 && p.covers((x, y)));
+                if destroyed {
+                    *c = ' ';
+                }
+            }
+        }
+        sprite
+    }
+
+    /// Damages a part, moving on to the next phase if the boss is weak enough
+    ///
+    /// Returns true if the part was the core and it was destroyed.
+    ///
+    /// # Arguments
+    /// * def: the boss's type
+    /// * part: the index of the part to damage
+    /// * damage: the health to take from the part
+    pub fn damage(&mut self, def: &BossDef, part: usize, damage: usize) -> bool {
+        self.parts[part] = self.parts[part].saturating_sub(damage);
+        self.body.health = self.parts.iter().sum();
+
+        let percent = self.body.health * 100 / def.health();
+        let phase = def
+            .phases
+            .iter()
+            .rposition(|p| percent <= p.at)
+            .unwrap_or(0);
+        if phase > self.phase {
+            // Each phase starts its movement and weapon afresh
+            self.phase = phase;
+            self.body.moves = 0;
+            self.body.wait = 0;
+            self.body.reload = 0;
+            self.body.volleys = 0;
+            self.body.anchor = self.body.pos;
+        }
+        self.parts[part] == 0 && def.parts[part].name == CORE
+    }
+
+    /// Returns where each part still standing fires from, just below its middle
+    ///
+    /// # Arguments
+    /// * def: the boss's type
+    pub fn muzzles(&self, def: &BossDef) -> Vec<Vec2> {
+        def.parts
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.parts[*i] > 0)
+            .map(|(_, p)| {
+                self.body.pos
+                    + Vec2::new((p.pos.0 + p.size.0 / 2) as f64, (p.pos.1 + p.size.1) as f64)
+            })
+            .collect()
+    }
+
+    /// Returns a cell relative to the upper-left corner of the boss, if it is
+    /// within the boss's sprite
+    ///
+    /// # Arguments
+    /// * (x, y): the cell on the map
+    fn local(&self, (x, y): (i32, i32)) -> Option<(usize, usize)> {
+        let (left, top) = self.body.pos.cell();
+        let (dx, dy) = (x - left, y - top);
+        if dx >= 0
+            && dy >= 0
+            && (dx as usize) < self.body.size.0
+            && (dy as usize) < self.body.size.1
+        {
+            Some((dx as usize, dy as usize))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text_engine::data;
+
+    // Reads the only boss in a data file
+    fn read(text: &str) -> Result<BossDef, DataError> {
+        let sections = data::parse("test.txt", text)?;
+        BossDef::read("warden", &sections[0], &Library::new())
+    }
+
+    #[test]
+    fn reads_boss() {
+        let boss = read(
+            "[boss warden]\n\
+             sprite = <==##==>\n\
+             sprite = <==##==>\n\
+             part = core 3 0 2 2 10\n\
+             part = left 0 0 2 1 4\n\
+             phase = 100 | 300 | strafe 10 0 | none\n\
+             phase = 50 | 200 | zigzag | none\n\
+             score = 500\n",
+        )
+        .unwrap();
+        assert_eq!(boss.name, "warden");
+        assert_eq!(boss.parts.len(), 2);
+        assert_eq!((boss.parts[0].pos, boss.parts[0].size), ((3, 0), (2, 2)));
+        assert!(boss.parts[1].covers((1, 0)) && !boss.parts[1].covers((1, 1)));
+        assert_eq!(boss.health(), 14);
+        assert_eq!(
+            boss.phases.iter().map(|p| p.at).collect::<Vec<_>>(),
+            vec![100, 50]
+        );
+        assert_eq!(boss.score, 500);
+    }
+
+    #[test]
+    fn rejects_bad_bosses() {
+        let error = |text: &str| read(text).err().unwrap().to_string();
+        let sprite = "[boss warden]\nsprite = <==##==>\n";
+        assert_eq!(
+            error(&format!("{}part = core 7 0 2 1 10\n", sprite)),
+            "test.txt:3: part core does not fit in the sprite"
+        );
+        assert_eq!(
+            error(&format!("{}part = core 3 0 2 1\n", sprite)),
+            "test.txt:3: invalid part core 3 0 2 1"
+        );
+        assert_eq!(
+            error(&format!("{}phase = 50 | 300 | straight | none\n", sprite)),
+            "test.txt:3: phases must start at 100 and go down in order"
+        );
+        assert_eq!(
+            error(&format!("{}phase = 100 | 0 | straight | none\n", sprite)),
+            "test.txt:3: invalid speed 0"
+        );
+        assert_eq!(
+            error(&format!(
+                "{}part = left 0 0 2 1 4\nphase = 100 | 300 | straight | none\n",
+                sprite
+            )),
+            "test.txt:1: [boss warden] needs a core part and at least one phase"
+        );
+    }
+}
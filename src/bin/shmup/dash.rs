@@ -0,0 +1,63 @@
+use text_engine::Vec2;
+
+use crate::{DASH_COOLDOWN, TRAIL_TIME};
+
+/// The player's dash, and which way it would send them
+#[derive(Clone)]
+pub struct Dash {
+    pub facing: (i32, i32), // Columns and rows of the last way the player moved
+    pub cooldown: u128,     // Milliseconds left before the player can dash again
+    pub trail: Vec<(Vec2, u128)>, // After-images and milliseconds left to show each
+}
+impl Default for Dash {
+    fn default() -> Self {
+        Self {
+            facing: (0, -1),
+            cooldown: 0,
+            trail: Vec::new(),
+        }
+    }
+}
+impl Dash {
+    /// Turns the dash to face the way the player last moved
+    ///
+    /// Moves given in the same tick add up, so two keys held together face
+    /// diagonally. Moves that cancel out leave it facing the same way.
+    ///
+    /// # Arguments
+    /// * moves: the columns and rows of each move given this tick
+    pub fn face(&mut self, moves: &[(i32, i32)]) {
+        let (x, y) = moves
+            .iter()
+            .fold((0, 0), |(x, y), (dx, dy)| (x + dx, y + dy));
+        if (x, y) != (0, 0) {
+            self.facing = (x.signum(), y.signum());
+        }
+    }
+
+    /// Returns true if the dash has cooled down
+    pub fn ready(&self) -> bool {
+        self.cooldown == 0
+    }
+
+    /// Starts the cooldown and leaves after-images in the cells passed through
+    ///
+    /// # Arguments
+    /// * cells: the cells the player dashed through, not counting where they stopped
+    pub fn start(&mut self, cells: Vec<Vec2>) {
+        self.cooldown = DASH_COOLDOWN;
+        self.trail = cells.into_iter().map(|c| (c, TRAIL_TIME)).collect();
+    }
+
+    /// Counts down the cooldown and fades the after-images
+    ///
+    /// # Arguments
+    /// * ms: milliseconds passed
+    pub fn tick(&mut self, ms: u128) {
+        self.cooldown = self.cooldown.saturating_sub(ms);
+        for (_, time) in &mut self.trail {
+            *time = time.saturating_sub(ms);
+        }
+        self.trail.retain(|(_, time)| *time > 0);
+    }
+}
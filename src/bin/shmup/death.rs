@@ -0,0 +1,78 @@
+use text_engine::pattern::{Library, Pattern};
+use text_engine::Vec2;
+
+/// What an enemy leaves behind when it is destroyed
+pub enum OnDeath {
+    Split {
+        child: String, // Name of the enemy type it splits into
+        count: usize,
+    },
+    Explode {
+        pattern: Pattern, // Fired once from its middle
+        damage: usize,    // Health each bullet takes from the player
+    },
+    Mine,         // A mine that stays until it is shot or run into
+    Hazard(u128), // Milliseconds the cells it covered hurt to touch
+}
+impl OnDeath {
+    /// Returns what an enemy leaves behind from its description in a data
+    /// file, or None for `none`
+    ///
+    /// The description is one of `split TYPE COUNT`, `explode PATTERN
+    /// [DAMAGE]`, `mine` or `hazard MS`, e.g. `split shard 2`.
+    ///
+    /// # Arguments
+    /// * text: the description
+    /// * patterns: the patterns an explosion can use
+    pub fn parse(text: &str, patterns: &Library) -> Result<Option<Self>, String> {
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        let name = parts.first().copied().unwrap_or("none");
+        let number = |index: usize| -> Result<u128, String> {
+            match parts[index].parse() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(format!("invalid settings for {}", name)),
+            }
+        };
+        let on_death = match (name, parts.len()) {
+            ("none", 1) => return Ok(None),
+            ("split", 3) => OnDeath::Split {
+                child: parts[1].to_string(),
+                count: match parts[2].parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid settings for {}", name)),
+                },
+            },
+            ("explode", 2) | ("explode", 3) => OnDeath::Explode {
+                pattern: patterns
+                    .get(parts[1])
+                    .ok_or_else(|| format!("unknown pattern {}", parts[1]))?
+                    .clone(),
+                damage: match parts.len() {
+                    3 => number(2)? as usize,
+                    _ => 1,
+                },
+            },
+            ("mine", 1) => OnDeath::Mine,
+            ("hazard", 2) => OnDeath::Hazard(number(1)?),
+            ("none", _) | ("split", _) | ("explode", _) | ("mine", _) | ("hazard", _) => {
+                return Err(format!("wrong number of settings for {}", name))
+            }
+            (other, _) => return Err(format!("unknown on-death {}", other)),
+        };
+        Ok(Some(on_death))
+    }
+}
+
+/// A cell that hurts the player to touch, left behind by a destroyed enemy
+#[derive(Clone)]
+pub struct Hazard {
+    pub pos: Vec2,
+    pub time: Option<u128>, // Milliseconds left, or None for a mine
+}
+impl Hazard {
+    /// Returns true if the hazard is a mine, which stays until it is shot or
+    /// run into
+    pub fn is_mine(&self) -> bool {
+        self.time.is_none()
+    }
+}
@@ -0,0 +1,53 @@
+use text_engine::Vec2;
+
+use crate::{DRONE_SPACING, MAX_DRONES};
+
+// Where drones hold while the player is focused, relative to the player
+const FIXED: [(f64, f64); MAX_DRONES] = [(-2.0, 0.0), (2.0, 0.0), (-4.0, 1.0), (4.0, 1.0)];
+
+/// Option drones flying with the player
+///
+/// Drones follow the player's recent positions one after another, or hold
+/// still either side of them while they are focused. They fire whenever the
+/// player's weapon does and stop enemy bullets that run into them.
+#[derive(Clone, Default)]
+pub struct Drones {
+    pub count: usize,       // Up to `MAX_DRONES`
+    pub history: Vec<Vec2>, // The player's recent positions, latest first
+}
+impl Drones {
+    /// Notes where the player is, if they have moved
+    ///
+    /// # Arguments
+    /// * pos: the player's position
+    pub fn follow(&mut self, pos: Vec2) {
+        if self
+            .history
+            .first()
+            .is_none_or(|last| last.cell() != pos.cell())
+        {
+            self.history.insert(0, pos);
+            self.history.truncate(MAX_DRONES * DRONE_SPACING + 1);
+        }
+    }
+
+    /// Returns where each drone is
+    ///
+    /// # Arguments
+    /// * player: the player's position
+    /// * focused: whether the player is focused
+    pub fn positions(&self, player: Vec2, focused: bool) -> Vec<Vec2> {
+        (0..self.count)
+            .map(|i| {
+                if focused {
+                    let (x, y) = FIXED[i];
+                    player + Vec2::new(x, y)
+                } else {
+                    // Drones bunch up behind the player until they have moved far enough
+                    let back = ((i + 1) * DRONE_SPACING).min(self.history.len().saturating_sub(1));
+                    self.history.get(back).copied().unwrap_or(player)
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,573 @@
+use rand::Rng;
+use std::fs;
+use std::path::{Path, PathBuf};
+use text_engine::data::{self, DataError, Section};
+use text_engine::pattern::{Library, Pattern};
+use text_engine::rng::GameRng;
+use text_engine::storage::Storage;
+use text_engine::Sprite;
+
+use crate::boss::BossDef;
+use crate::death::OnDeath;
+use crate::formation::PathDef;
+use crate::level::Level;
+use crate::movement::{self, Movement};
+use crate::pickup::{self, DropRate};
+use crate::{HEIGHT, WIDTH};
+
+// Enemy types, bosses and bullet patterns that ship with the game
+const BUILTIN_FILES: [(&str, &str); 4] = [
+    ("patterns.txt", include_str!("../../../data/patterns.txt")),
+    ("paths.txt", include_str!("../../../data/paths.txt")),
+    ("enemies.txt", include_str!("../../../data/enemies.txt")),
+    ("bosses.txt", include_str!("../../../data/bosses.txt")),
+];
+
+// Levels that ship with the game, played in this order
+const BUILTIN_LEVELS: [(&str, &str); 5] = [
+    (
+        "1-first-contact",
+        include_str!("../../../data/levels/1-first-contact.txt"),
+    ),
+    (
+        "2-crossfire",
+        include_str!("../../../data/levels/2-crossfire.txt"),
+    ),
+    ("3-swarm", include_str!("../../../data/levels/3-swarm.txt")),
+    (
+        "4-warden",
+        include_str!("../../../data/levels/4-warden.txt"),
+    ),
+    ("5-hive", include_str!("../../../data/levels/5-hive.txt")),
+];
+
+// Folder in the config directory with extra enemy types and patterns
+const ENEMY_DIR: &str = "enemies";
+
+// Folder in the config directory with extra levels
+const LEVEL_DIR: &str = "levels";
+
+// Section names of bullet patterns start with this
+const PATTERN_PREFIX: &str = "pattern ";
+
+// Section names of bosses start with this
+const BOSS_PREFIX: &str = "boss ";
+
+// Section names of entry paths start with this
+const PATH_PREFIX: &str = "path ";
+
+/// How a type of enemy shoots
+pub struct EnemyWeapon {
+    pub pattern: Pattern,
+    pub interval: u128, // Milliseconds between volleys
+    pub damage: usize,  // Health each bullet takes from the player
+}
+impl EnemyWeapon {
+    /// Returns a weapon from its description in a data file, or None for `none`
+    ///
+    /// The description is the name of a pattern, then the milliseconds between
+    /// volleys and the damage each bullet does, e.g. `aimed 2000 1`.
+    ///
+    /// # Arguments
+    /// * text: the description
+    /// * patterns: the patterns the weapon can use
+    pub fn parse(text: &str, patterns: &Library) -> Result<Option<Self>, String> {
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        let name = parts.first().copied().unwrap_or("none");
+        if name == "none" && parts.len() <= 1 {
+            return Ok(None);
+        }
+        let pattern = patterns
+            .get(name)
+            .ok_or_else(|| format!("unknown pattern {}", name))?;
+        let interval = parts.get(1).map_or(Ok(1500), |p| p.parse());
+        let damage = parts.get(2).map_or(Ok(1), |p| p.parse());
+        match (interval, damage) {
+            (Ok(interval), Ok(damage)) if interval > 0 && damage > 0 && parts.len() <= 3 => {
+                Ok(Some(Self {
+                    pattern: pattern.clone(),
+                    interval,
+                    damage,
+                }))
+            }
+            _ => Err(format!("invalid settings for weapon {}", name)),
+        }
+    }
+}
+
+/// A type of enemy, as described in a data file
+pub struct EnemyDef {
+    pub name: String,
+    pub sprite: Sprite,
+    pub chipped: Sprite, // Shown once it is down to half its health
+    pub health: usize,   // Damage it takes to destroy
+    pub speed: u128,     // Milliseconds between moves
+    pub movement: Box<dyn Movement>,
+    pub weapon: Option<EnemyWeapon>,
+    pub on_death: Option<OnDeath>, // What it leaves behind when destroyed
+    pub drops: Vec<DropRate>,      // Pickups it can release when destroyed
+    pub score: i32,                // Points for destroying it
+    pub spawn: u32,                // Weight for the random spawner, 0 for never
+}
+impl EnemyDef {
+    /// Reads an enemy type from its section of a data file
+    ///
+    /// # Arguments
+    /// * section: the section describing the enemy
+    /// * patterns: the patterns its weapon can use
+    fn read(section: &Section, patterns: &Library) -> Result<Self, DataError> {
+        section.check_keys(&[
+            "sprite", "chipped", "health", "speed", "movement", "weapon", "on-death", "drop",
+            "score", "spawn",
+        ])?;
+
+        let rows = section.get_all("sprite");
+        let sprite = Sprite::new(&rows);
+        let (width, height) = sprite.size();
+        if width == 0 || width > WIDTH - 2 || height > HEIGHT / 2 {
+            return Err(section.error(
+                section.line,
+                &format!("[{}] needs a sprite that fits the map", section.name),
+            ));
+        }
+
+        let line = |key: &str| {
+            section
+                .fields
+                .iter()
+                .find(|f| f.key == key)
+                .map_or(section.line, |f| f.line)
+        };
+        let chipped = match section.get_all("chipped") {
+            rows if rows.is_empty() => chip(&sprite),
+            rows => Sprite::new(&rows),
+        };
+        if chipped.size() != sprite.size() {
+            return Err(section.error(
+                line("chipped"),
+                &format!(
+                    "[{}] needs a chipped sprite the same size as its sprite",
+                    section.name
+                ),
+            ));
+        }
+        let movement = movement::parse(section.get("movement").unwrap_or("straight"))
+            .map_err(|e| section.error(line("movement"), &e))?;
+        let weapon = EnemyWeapon::parse(section.get("weapon").unwrap_or("none"), patterns)
+            .map_err(|e| section.error(line("weapon"), &e))?;
+        let on_death = OnDeath::parse(section.get("on-death").unwrap_or("none"), patterns)
+            .map_err(|e| section.error(line("on-death"), &e))?;
+        let mut drops = Vec::new();
+        for field in section.fields.iter().filter(|f| f.key == "drop") {
+            drops
+                .push(pickup::parse_drop(&field.value).map_err(|e| section.error(field.line, &e))?);
+        }
+        if drops.iter().map(|d| d.chance).sum::<u32>() > 100 {
+            return Err(section.error(
+                line("drop"),
+                &format!("[{}] has drop chances adding up to over 100", section.name),
+            ));
+        }
+
+        let def = Self {
+            name: section.name.clone(),
+            sprite,
+            chipped,
+            health: section.parse("health")?,
+            speed: section.parse("speed")?,
+            movement,
+            weapon,
+            on_death,
+            drops,
+            score: section.parse_or("score", 0)?,
+            spawn: section.parse_or("spawn", 0)?,
+        };
+        if def.health == 0 || def.speed == 0 {
+            return Err(section.error(
+                section.line,
+                &format!("[{}] needs health and speed above 0", section.name),
+            ));
+        }
+        Ok(def)
+    }
+
+    /// Returns the sprite to draw an enemy of this type with
+    ///
+    /// # Arguments
+    /// * health: the health the enemy has left
+    pub fn sprite_for(&self, health: usize) -> &Sprite {
+        if health * 2 <= self.health {
+            &self.chipped
+        } else {
+            &self.sprite
+        }
+    }
+}
+
+/// Returns a sprite with its letters in lower case, for enemy types without
+/// their own chipped sprite
+///
+/// # Arguments
+/// * sprite: the undamaged sprite
+fn chip(sprite: &Sprite) -> Sprite {
+    let mut chipped = sprite.clone();
+    for c in chipped.rows.iter_mut().flatten() {
+        *c = c.to_ascii_lowercase();
+    }
+    chipped
+}
+
+/// Every known type of enemy and boss, the bullet patterns and paths they
+/// use, and the levels they appear in
+///
+/// Enemies, bosses and levels are referred to by their index here. Saves
+/// refer to them by name, so they survive types being added or reordered.
+pub struct Registry {
+    defs: Vec<EnemyDef>,
+    bosses: Vec<BossDef>,
+    levels: Vec<Level>,
+    paths: Vec<PathDef>,
+    pub patterns: Library,
+}
+impl Registry {
+    /// Returns the built-in enemy types, plus any in the config directory
+    ///
+    /// Types, bosses, patterns and paths in the config directory replace
+    /// built-in ones with the same name. All patterns are read before any
+    /// enemy types, so a type can use a pattern from any file.
+    ///
+    /// Levels in the config directory are named after their file. They
+    /// replace the built-in level with the same name, or are played after the
+    /// built-in ones in order of their names.
+    ///
+    /// # Arguments
+    /// * storage: where configuration is kept
+    pub fn load(storage: &Storage) -> Result<Self, DataError> {
+        let dir = storage.config_path(ENEMY_DIR);
+        let mut extra = Vec::new();
+        for file in &list_files(&dir) {
+            extra.extend(read_file(file)?);
+        }
+        Self::read(&dir, extra, &list_files(&storage.config_path(LEVEL_DIR)))
+    }
+
+    /// Returns the built-in enemy types and levels only, whatever is in the
+    /// config directory
+    #[cfg(test)]
+    pub fn builtin() -> Self {
+        Self::read(Path::new(ENEMY_DIR), Vec::new(), &[]).unwrap()
+    }
+
+    /// Returns the built-in enemy types, plus extra ones read from data files
+    ///
+    /// # Arguments
+    /// * dir: where the extra types came from, used in errors
+    /// * extra: the sections of the extra data files, in order
+    /// * level_files: extra level scripts, in order
+    fn read(dir: &Path, extra: Vec<Section>, level_files: &[PathBuf]) -> Result<Self, DataError> {
+        let mut sections = Vec::new();
+        for (file, text) in BUILTIN_FILES.iter() {
+            sections.extend(data::parse(&format!("{} (built-in)", file), text)?);
+        }
+        sections.extend(extra);
+
+        let mut registry = Self {
+            defs: Vec::new(),
+            bosses: Vec::new(),
+            levels: Vec::new(),
+            paths: Vec::new(),
+            patterns: Library::new(),
+        };
+        for section in &sections {
+            if let Some(name) = section.name.strip_prefix(PATTERN_PREFIX) {
+                let pattern = Pattern::read(section, &registry.patterns)?;
+                registry.patterns.add(name.trim(), pattern);
+            }
+        }
+        for section in &sections {
+            if let Some(name) = section.name.strip_prefix(BOSS_PREFIX) {
+                let boss = BossDef::read(name.trim(), section, &registry.patterns)?;
+                match registry.find_boss(&boss.name) {
+                    Some(index) => registry.bosses[index] = boss,
+                    None => registry.bosses.push(boss),
+                }
+            } else if let Some(name) = section.name.strip_prefix(PATH_PREFIX) {
+                let path = PathDef::read(name.trim(), section)?;
+                match registry.find_path(&path.name) {
+                    Some(index) => registry.paths[index] = path,
+                    None => registry.paths.push(path),
+                }
+            } else if !section.name.starts_with(PATTERN_PREFIX) {
+                let def = EnemyDef::read(section, &registry.patterns)?;
+                match registry.find(&def.name) {
+                    Some(index) => registry.defs[index] = def,
+                    None => registry.defs.push(def),
+                }
+            }
+        }
+
+        // Types can split into types from any file, so they are checked once all are read
+        for def in &registry.defs {
+            let (child, count) = match &def.on_death {
+                Some(OnDeath::Split { child, count }) => (child, *count),
+                _ => continue,
+            };
+            let problem = match registry.find(child) {
+                None => format!("unknown enemy type {}", child),
+                Some(_) if *child == def.name => format!("{} cannot split into itself", child),
+                // The children are side by side, so they have to fit between the walls
+                Some(index) => {
+                    let width = registry.defs[index].sprite.size().0;
+                    if count <= WIDTH && count * (width + 1) - 1 <= WIDTH - 2 {
+                        continue;
+                    }
+                    format!("{} {} enemies do not fit on the map", count, child)
+                }
+            };
+            // Later sections replace earlier ones, so the type came from the last
+            if let Some(section) = sections.iter().rev().find(|s| s.name == def.name) {
+                let line = section
+                    .fields
+                    .iter()
+                    .find(|f| f.key == "on-death")
+                    .map_or(section.line, |f| f.line);
+                return Err(section.error(line, &problem));
+            }
+        }
+
+        if registry.defs.iter().all(|d| d.spawn == 0) {
+            return Err(DataError::new(
+                &dir.display().to_string(),
+                0,
+                "no enemy type has a spawn weight above 0",
+            ));
+        }
+
+        for (name, text) in BUILTIN_LEVELS.iter() {
+            let file = format!("levels/{}.txt (built-in)", name);
+            let level = Level::parse(name, &file, text, &registry)?;
+            registry.levels.push(level);
+        }
+        for file in level_files {
+            let name = file.file_stem().unwrap_or_default().to_string_lossy();
+            let path = file.display().to_string();
+            let text =
+                fs::read_to_string(file).map_err(|e| DataError::new(&path, 0, &e.to_string()))?;
+            let level = Level::parse(&name, &path, &text, &registry)?;
+            match registry.find_level(&level.name) {
+                Some(index) => registry.levels[index] = level,
+                None => registry.levels.push(level),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Returns an enemy type by index
+    ///
+    /// # Arguments
+    /// * index: the index of the type
+    pub fn get(&self, index: usize) -> &EnemyDef {
+        &self.defs[index]
+    }
+
+    /// Returns the index of an enemy type by name
+    ///
+    /// # Arguments
+    /// * name: the name of the type
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.defs.iter().position(|d| d.name == name)
+    }
+
+    /// Returns a boss by index
+    ///
+    /// # Arguments
+    /// * index: the index of the boss
+    pub fn boss(&self, index: usize) -> &BossDef {
+        &self.bosses[index]
+    }
+
+    /// Returns the index of a boss by name
+    ///
+    /// # Arguments
+    /// * name: the name of the boss
+    pub fn find_boss(&self, name: &str) -> Option<usize> {
+        self.bosses.iter().position(|b| b.name == name)
+    }
+
+    /// Returns the number of bosses
+    pub fn boss_count(&self) -> usize {
+        self.bosses.len()
+    }
+
+    /// Returns an entry path by index
+    ///
+    /// # Arguments
+    /// * index: the index of the path
+    pub fn path(&self, index: usize) -> &PathDef {
+        &self.paths[index]
+    }
+
+    /// Returns the index of an entry path by name
+    ///
+    /// # Arguments
+    /// * name: the name of the path
+    pub fn find_path(&self, name: &str) -> Option<usize> {
+        self.paths.iter().position(|p| p.name == name)
+    }
+
+    /// Returns a level by index
+    ///
+    /// # Arguments
+    /// * index: the index of the level
+    pub fn level(&self, index: usize) -> &Level {
+        &self.levels[index]
+    }
+
+    /// Returns the index of a level by name
+    ///
+    /// # Arguments
+    /// * name: the name of the level
+    pub fn find_level(&self, name: &str) -> Option<usize> {
+        self.levels.iter().position(|l| l.name == name)
+    }
+
+    /// Returns the number of levels
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Picks a type for the random spawner, weighted by spawn weight
+    ///
+    /// # Arguments
+    /// * rng: the run's random number generator
+    pub fn pick(&self, rng: &mut GameRng) -> usize {
+        let total: u32 = self.defs.iter().map(|d| d.spawn).sum();
+        let mut roll = rng.gen_range(0, total);
+        for (index, def) in self.defs.iter().enumerate() {
+            if roll < def.spawn {
+                return index;
+            }
+            roll -= def.spawn;
+        }
+        unreachable!()
+    }
+}
+
+/// Returns the `.txt` files in a folder, sorted by name
+///
+/// A missing folder has no files.
+///
+/// # Arguments
+/// * dir: the folder to look in
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+/// Reads the sections of a data file on disk
+///
+/// # Arguments
+/// * path: the file to read
+fn read_file(path: &Path) -> Result<Vec<Section>, DataError> {
+    let name = path.display().to_string();
+    let text = fs::read_to_string(path).map_err(|e| DataError::new(&name, 0, &e.to_string()))?;
+    data::parse(&name, &text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads the only enemy type in a data file
+    fn read(text: &str) -> Result<EnemyDef, DataError> {
+        let sections = data::parse("test.txt", text)?;
+        EnemyDef::read(&sections[0], &Library::new())
+    }
+
+    #[test]
+    fn reads_enemy_type() {
+        let def = read(
+            "[grunt]\n\
+             sprite = XX\n\
+             sprite = XX\n\
+             health = 4\n\
+             speed = 300\n\
+             movement = zigzag 2\n\
+             score = 10\n\
+             spawn = 5\n",
+        )
+        .unwrap();
+        assert_eq!(def.name, "grunt");
+        assert_eq!(def.sprite.size(), (2, 2));
+        assert_eq!(def.chipped.rows[0], vec!['x', 'x']);
+        assert_eq!(
+            (def.health, def.speed, def.score, def.spawn),
+            (4, 300, 10, 5)
+        );
+        assert!(def.weapon.is_none());
+        assert!(std::ptr::eq(def.sprite_for(3), &def.sprite));
+        assert!(std::ptr::eq(def.sprite_for(2), &def.chipped));
+    }
+
+    // Loads the built-in types plus those in a data file
+    fn load_with(text: &str) -> Result<Registry, DataError> {
+        let extra = data::parse("test.txt", text)?;
+        Registry::read(Path::new(ENEMY_DIR), extra, &[])
+    }
+
+    #[test]
+    fn checks_splits_once_all_types_are_read() {
+        let splitter = |on_death: &str| {
+            format!(
+                "[splitter]\nsprite = X\nhealth = 1\nspeed = 300\non-death = {}\n",
+                on_death
+            )
+        };
+        assert!(load_with(&splitter("split shard 3")).is_ok());
+        let error = |on_death: &str| load_with(&splitter(on_death)).err().unwrap().to_string();
+        assert_eq!(
+            error("split nobody 2"),
+            "test.txt:5: unknown enemy type nobody"
+        );
+        assert_eq!(
+            error("split splitter 2"),
+            "test.txt:5: splitter cannot split into itself"
+        );
+        assert_eq!(
+            error("split shard 30"),
+            "test.txt:5: 30 shard enemies do not fit on the map"
+        );
+        assert_eq!(
+            error("split shard 99999999999999999999999"),
+            "test.txt:5: invalid settings for split"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_enemy_types() {
+        let error = |text: &str| read(text).err().unwrap().to_string();
+        assert_eq!(
+            error("[grunt]\nsprite = X\nhealth = 0\nspeed = 300\n"),
+            "test.txt:1: [grunt] needs health and speed above 0"
+        );
+        assert_eq!(
+            error("[grunt]\nsprite = X\nhealth = 1\nspeed = 300\nmovement = teleport\n"),
+            "test.txt:5: unknown movement teleport"
+        );
+        assert_eq!(
+            error("[grunt]\nsprite = X\nhealth = 1\nspeed = 300\nweapon = laser\n"),
+            "test.txt:5: unknown pattern laser"
+        );
+        assert_eq!(
+            error("[grunt]\nhealth = 1\nspeed = 300\n"),
+            "test.txt:1: [grunt] needs a sprite that fits the map"
+        );
+    }
+}
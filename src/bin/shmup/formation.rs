@@ -0,0 +1,197 @@
+use text_engine::data::{DataError, Section};
+use text_engine::path::Path;
+use text_engine::{Enemy, Vec2};
+
+use crate::WIDTH;
+
+// Grid of slots at the top of the map that enemies settle into. A slot is
+// held by the enemy in it, so it opens up again as soon as that enemy is
+// destroyed or peels off.
+const COLUMNS: usize = 6;
+const ROWS: usize = 3;
+const SLOT_SIZE: (usize, usize) = (4, 3); // Space for each enemy, with a gap
+const TOP: usize = 2; // Row of the top of the grid
+
+/// Cells per second an enemy glides into its formation slot at
+pub const SETTLE_SPEED: f64 = 8.0;
+
+/// A named path that enemies fly in along, as described in a data file
+pub struct PathDef {
+    pub name: String,
+    pub path: Path,
+    pub speed: f64, // Cells per second
+}
+impl PathDef {
+    /// Reads a path from its section of a data file
+    ///
+    /// Paths look like:
+    /// ```text
+    /// [path swoop]
+    /// curve = catmull-rom
+    /// point = -3 4
+    /// point = 10 12
+    /// speed = 12
+    /// ```
+    ///
+    /// # Arguments
+    /// * name: the name of the path, without the `path` prefix
+    /// * section: the section describing the path
+    pub fn read(name: &str, section: &Section) -> Result<Self, DataError> {
+        section.check_keys(&["curve", "point", "speed"])?;
+
+        let mut points = Vec::new();
+        for field in section.fields.iter().filter(|f| f.key == "point") {
+            let xy: Vec<f64> = field
+                .value
+                .split_whitespace()
+                .filter_map(|n| n.parse().ok())
+                .collect();
+            if xy.len() != 2 || field.value.split_whitespace().count() != 2 {
+                return Err(section.error(field.line, &format!("invalid point {}", field.value)));
+            }
+            points.push(Vec2::new(xy[0], xy[1]));
+        }
+
+        let path = match section.get("curve").unwrap_or("catmull-rom") {
+            "catmull-rom" if points.len() >= 2 => Path::catmull_rom(&points),
+            "bezier" if points.len() >= 4 && points.len() % 3 == 1 => Path::bezier(&points),
+            "catmull-rom" | "bezier" => {
+                return Err(section.error(
+                    section.line,
+                    &format!(
+                        "[{}] needs at least 2 points for catmull-rom, or 3 per curve plus 1 for bezier",
+                        section.name
+                    ),
+                ))
+            }
+            other => {
+                return Err(section.error(section.line, &format!("unknown curve {}", other)))
+            }
+        };
+
+        let speed = section.parse_or("speed", 12.0)?;
+        if speed <= 0.0 {
+            return Err(section.error(
+                section.line,
+                &format!("[{}] needs a speed above 0", section.name),
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            speed,
+        })
+    }
+
+    /// Returns the point a distance along the path, or None past its end
+    ///
+    /// # Arguments
+    /// * distance: cells travelled from the start of the path
+    /// * mirrored: flip the path left to right
+    /// * width: the width of the enemy following the path
+    pub fn at(&self, distance: f64, mirrored: bool, width: usize) -> Option<Vec2> {
+        let point = self.path.at(distance)?;
+        if mirrored {
+            // Mirror the enemy's left edge so it stays inside the same walls
+            Some(Vec2::new((WIDTH - width) as f64 - point.x, point.y))
+        } else {
+            Some(point)
+        }
+    }
+}
+
+/// Returns true if an enemy of a size fits in a formation slot
+///
+/// # Arguments
+/// * size: the width and height of the enemy
+pub fn fits(size: (usize, usize)) -> bool {
+    size.0 < SLOT_SIZE.0 && size.1 < SLOT_SIZE.1
+}
+
+/// Returns the upper-left corner of a formation slot
+///
+/// # Arguments
+/// * slot: the index of the slot, left to right from the top row
+pub fn slot_position(slot: usize) -> Vec2 {
+    let left = (WIDTH - COLUMNS * SLOT_SIZE.0) / 2 + 1;
+    Vec2::new(
+        (left + slot % COLUMNS * SLOT_SIZE.0) as f64,
+        (TOP + slot / COLUMNS * SLOT_SIZE.1) as f64,
+    )
+}
+
+/// Returns the first formation slot no enemy holds, if there is one
+///
+/// # Arguments
+/// * enemies: the enemies in play
+pub fn open_slot(enemies: &[Enemy]) -> Option<usize> {
+    (0..COLUMNS * ROWS).find(|slot| enemies.iter().all(|e| e.slot != Some(*slot)))
+}
+
+/// Returns true if an enemy has reached its formation slot and is waiting there
+///
+/// # Arguments
+/// * enemy: the enemy to check
+pub fn settled(enemy: &Enemy) -> bool {
+    enemy.path.is_none() && enemy.slot.is_some_and(|s| enemy.pos == slot_position(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text_engine::data;
+
+    // Reads the only path in a data file
+    fn read(text: &str) -> Result<PathDef, DataError> {
+        let sections = data::parse("test.txt", text)?;
+        PathDef::read("swoop", &sections[0])
+    }
+
+    #[test]
+    fn reads_path() {
+        let path = read(
+            "[path swoop]\n\
+             point = 2 -3\n\
+             point = 10 8\n\
+             point = 20 4\n\
+             speed = 6\n",
+        )
+        .unwrap();
+        assert_eq!((path.name.as_str(), path.speed), ("swoop", 6.0));
+        assert!(path.at(0.0, false, 1) == Some(Vec2::new(2.0, -3.0)));
+        assert!(path.at(0.0, true, 3) == Some(Vec2::new((WIDTH - 3 - 2) as f64, -3.0)));
+        assert!(path.at(1000.0, false, 1).is_none());
+
+        let bezier = read(
+            "[path swoop]\n\
+             curve = bezier\n\
+             point = 1 1\n\
+             point = 1 6\n\
+             point = 6 6\n\
+             point = 6 1\n",
+        )
+        .unwrap();
+        assert!(bezier.path.cells.last() == Some(&Vec2::new(6.0, 1.0)));
+    }
+
+    #[test]
+    fn rejects_bad_paths() {
+        let error = |text: &str| read(text).err().unwrap().to_string();
+        assert_eq!(
+            error("[path swoop]\npoint = 1 2\npoint = 3\n"),
+            "test.txt:3: invalid point 3"
+        );
+        assert_eq!(
+            error("[path swoop]\ncurve = bezier\npoint = 1 2\npoint = 3 4\n"),
+            "test.txt:1: [path swoop] needs at least 2 points for catmull-rom, or 3 per curve plus 1 for bezier"
+        );
+        assert_eq!(
+            error("[path swoop]\ncurve = spiral\n"),
+            "test.txt:1: unknown curve spiral"
+        );
+        assert_eq!(
+            error("[path swoop]\npoint = 1 2\npoint = 3 4\nspeed = 0\n"),
+            "test.txt:1: [path swoop] needs a speed above 0"
+        );
+    }
+}
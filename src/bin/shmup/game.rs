@@ -0,0 +1,1949 @@
+use crossterm::event::KeyCode;
+use crossterm::style::Attribute;
+use rand::Rng;
+use std::io::{self, ErrorKind};
+use std::rc::Rc;
+use text_engine::input::Held;
+use text_engine::pattern::{glyph, Pattern};
+use text_engine::replay::Replay;
+use text_engine::rng::GameRng;
+use text_engine::*;
+
+use crate::bomb::Shockwave;
+use crate::boss::Boss;
+use crate::dash::Dash;
+use crate::death::{Hazard, OnDeath};
+use crate::drone::Drones;
+use crate::enemy::Registry;
+use crate::formation::{self, SETTLE_SPEED};
+use crate::level::{Event, Formation, Script};
+use crate::movement::{Movement, PEEL_DIVE};
+use crate::pickup::{Effects, Kind, Pickup};
+use crate::score::{Combo, Popup};
+use crate::weapon::{self, Gun, Shot};
+use crate::{
+    BEAM_HOLD, BEAM_SYM, BLINK, BOMBS, BOMB_DAMAGE, BOMB_SCORE, BOSS_GEN_SPD, BULLET_SPD,
+    BULLET_SYM, CAMPAIGN, DASH_DISTANCE, DASH_INVINCIBLE, DRONE_SYM, ENEMY_GEN_SPD, FAST_KILL,
+    FAST_KILL_BONUS, FIRE_HOLD, FOCUS_SPREAD, FOCUS_STRIDE, GRAZE_POINTS, HAZARD_SYM, HEIGHT,
+    HIT_FLASH, INVINCIBLE_TIME, LIFE_SCORE, LIVES, MAGNET_RADIUS, MAGNET_SPEED, MAX_BOMBS,
+    MAX_DRONES, MAX_HEALTH, MAX_LIVES, MESSAGE_TIME, MINE_SYM, MULTIPLIER, MULTIPLIER_TIME,
+    OVERKILL_BONUS, OVERSHIELD, PEEL_SPEED, PICKUP_SPEED, PLAYER_SYM, POPUP_SPEED, POPUP_TIME,
+    RESPAWN_SHIELD, SHIELD_TIME, SHOCKWAVE_SPEED, SHOCKWAVE_SYM, SPEED_TIME, WIDTH,
+};
+
+/// Length of one simulation tick in milliseconds
+pub const TICK_MS: u128 = 50;
+
+// Fire stays held for `FIRE_HOLD` after the key last repeats, so a charge
+// builds for that much longer than the key was really held
+const CHARGE_TAIL: u128 = FIRE_HOLD as u128;
+
+/// Returns the character for an enemy bullet, showing which way it is going
+///
+/// # Arguments
+/// * b: the bullet to draw
+fn enemy_glyph(b: &Bullet) -> char {
+    if b.delay > 0 {
+        glyph(Vec2::default())
+    } else {
+        glyph(b.velocity)
+    }
+}
+
+/// Fires one volley of an enemy's bullet pattern, dropping bullets that
+/// would start outside the walls
+///
+/// # Arguments
+/// * map: the current map
+/// * bullets: the bullets in play, to add the volley to
+/// * pattern: the pattern to fire
+/// * damage: the health each bullet takes from the player
+/// * volley: the number of the volley, starting at 0
+/// * muzzle: where the volley is fired from
+/// * target: what aimed patterns point at
+fn fire(
+    map: &Map,
+    bullets: &mut Vec<Bullet>,
+    pattern: &Pattern,
+    damage: usize,
+    volley: u64,
+    muzzle: Vec2,
+    target: Vec2,
+) {
+    for shot in pattern.volley(volley, muzzle, target) {
+        let pos = muzzle + shot.offset;
+        if map.inside(pos.cell()).is_some() {
+            let mut bullet = Bullet::new(pos, BULLET_SYM, Team::Enemy, shot.velocity);
+            bullet.delay = shot.delay;
+            bullet.damage = damage;
+            bullet.symbol = enemy_glyph(&bullet);
+            bullets.push(bullet);
+        }
+    }
+}
+
+/// What a player's bullet did to the cell it reached
+#[derive(PartialEq)]
+enum Hit {
+    Miss,
+    Stopped,   // Stopped by an enemy, a boss or its armour
+    Destroyed, // Destroyed an enemy or a mine
+}
+
+/// A player input, applied on the tick it was pressed
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    Up,
+    Down,
+    Left,
+    Right,
+    Fire,
+    Bomb,
+    Dash,
+    Focus,   // Held for the whole tick rather than pressed
+    Charge,  // Fire held for the whole tick
+    Release, // Fire let go
+}
+impl Command {
+    /// Returns the command bound to a key, if any
+    ///
+    /// # Arguments
+    /// * code: the key that was pressed
+    pub fn from_key(code: KeyCode) -> Option<Self> {
+        match code {
+            KeyCode::Char('w') => Some(Command::Up),
+            KeyCode::Char('s') => Some(Command::Down),
+            KeyCode::Char('a') => Some(Command::Left),
+            KeyCode::Char('d') => Some(Command::Right),
+            KeyCode::Up => Some(Command::Fire),
+            KeyCode::Down => Some(Command::Bomb),
+            KeyCode::Char(' ') => Some(Command::Dash),
+            _ => None,
+        }
+    }
+
+    /// Returns the command for fire being held or let go this tick, if either
+    ///
+    /// # Arguments
+    /// * keys: the keys seen lately
+    pub fn from_held(keys: &mut Held<KeyCode>) -> Option<Self> {
+        if keys.released().contains(&KeyCode::Up) {
+            Some(Command::Release)
+        } else if keys.is_held(KeyCode::Up) {
+            Some(Command::Charge)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the char used to store the command in a replay
+    pub fn to_char(self) -> char {
+        match self {
+            Command::Up => 'w',
+            Command::Down => 's',
+            Command::Left => 'a',
+            Command::Right => 'd',
+            Command::Fire => '^',
+            Command::Bomb => 'b',
+            Command::Dash => '>',
+            Command::Focus => 'f',
+            Command::Charge => 'c',
+            Command::Release => 'r',
+        }
+    }
+
+    /// Returns the command stored as a char in a replay
+    ///
+    /// # Arguments
+    /// * c: the stored char
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'w' => Some(Command::Up),
+            's' => Some(Command::Down),
+            'a' => Some(Command::Left),
+            'd' => Some(Command::Right),
+            '^' => Some(Command::Fire),
+            'b' => Some(Command::Bomb),
+            '>' => Some(Command::Dash),
+            'f' => Some(Command::Focus),
+            'c' => Some(Command::Charge),
+            'r' => Some(Command::Release),
+            _ => None,
+        }
+    }
+
+    /// Returns the columns and rows the command moves the player, if it is a move
+    pub fn direction(self) -> Option<(i32, i32)> {
+        match self {
+            Command::Up => Some((0, -1)),
+            Command::Down => Some((0, 1)),
+            Command::Left => Some((-1, 0)),
+            Command::Right => Some((1, 0)),
+            _ => None,
+        }
+    }
+}
+
+/// The full state of one run
+///
+/// Everything that happens in a run is decided by the seed and the commands
+/// given to `step`, so a run can be replayed exactly from its inputs. The
+/// map is redrawn from the entities at the end of every step.
+#[derive(Clone)]
+pub struct Game {
+    pub map: Map,
+    pub player: Player,
+    pub lives: usize,          // Lives left, counting the one being played
+    invincible: u128,          // Milliseconds left before the player can be hurt again
+    focused: bool,             // Whether the player was focused on the last tick
+    pub gun: Gun,              // The player's weapon
+    pub charge: u128,          // Milliseconds fire has counted as held, see `CHARGE_TAIL`
+    beam: Vec<(usize, usize)>, // Cells the player's beam filled on the last tick
+    beam_hold: u128,           // Milliseconds left the beam stays on without fire repeating
+    pub effects: Effects,      // Timed effects of pickups on the player
+    pub bombs: usize,          // Bombs the player has left
+    pub dash: Dash,
+    pub drones: Drones,
+    pub enemies: Vec<Enemy>,
+    pub bullets: Vec<Bullet>,
+    pub hazards: Vec<Hazard>, // Mines and hazards left by destroyed enemies
+    pub pickups: Vec<Pickup>,
+    pub score: i32,
+    pub combo: Combo,
+    popups: Vec<Popup>, // Points recently scored, floating up the map
+    pub seed: u64,
+    pub tick: u64, // Number of ticks played
+    pub defs: Rc<Registry>,
+    pub boss: Option<Boss>,          // The boss being fought, if any
+    shockwave: Option<Shockwave>,    // The shockwave of the last bomb, while it spreads
+    script: Option<Script>,          // Progress through the levels, or None for endless play
+    message: Option<(String, u128)>, // Text shown on the map and milliseconds left to show it
+    peel: u128, // Milliseconds between enemies peeling off the formation, 0 for never
+    peel_timer: u128, // Milliseconds since an enemy last peeled off
+    rng: GameRng,
+    new_enemy: u128, // Milliseconds since an enemy was last generated
+    new_boss: u128,  // Milliseconds of regular play since the last boss
+    bosses: u64,     // Number of bosses that have appeared
+}
+impl Game {
+    /// Returns a new run
+    ///
+    /// # Arguments
+    /// * seed: seed for enemy generation
+    /// * defs: the enemy types the run can use
+    pub fn new(seed: u64, defs: Rc<Registry>) -> Self {
+        let mut map = Map::new(WIDTH, HEIGHT);
+        map.generate();
+        let player = Player::new(
+            Vec2::new((WIDTH / 2) as f64, (HEIGHT - 2) as f64),
+            PLAYER_SYM,
+            MAX_HEALTH,
+        );
+        map.set((WIDTH / 2, HEIGHT - 2), player.symbol);
+        Self {
+            map,
+            player,
+            lives: LIVES,
+            invincible: 0,
+            focused: false,
+            gun: Gun::new(0),
+            charge: 0,
+            beam: Vec::new(),
+            beam_hold: 0,
+            effects: Effects::default(),
+            bombs: BOMBS,
+            dash: Dash::default(),
+            drones: Drones::default(),
+            enemies: Vec::new(),
+            bullets: Vec::new(),
+            hazards: Vec::new(),
+            pickups: Vec::new(),
+            score: 0,
+            combo: Combo::default(),
+            popups: Vec::new(),
+            seed,
+            tick: 0,
+            defs,
+            boss: None,
+            shockwave: None,
+            script: None,
+            message: None,
+            peel: 0,
+            peel_timer: 0,
+            rng: GameRng::new(seed),
+            new_enemy: 0,
+            new_boss: 0,
+            bosses: 0,
+        }
+    }
+
+    /// Returns a new run in a game mode, playing through the levels in the
+    /// campaign and spawning enemies at random in any other
+    ///
+    /// # Arguments
+    /// * seed: seed for enemy generation
+    /// * defs: the enemy types and levels the run can use
+    /// * mode: the game mode
+    pub fn for_mode(seed: u64, defs: Rc<Registry>, mode: &str) -> Self {
+        let mut game = Game::new(seed, defs);
+        if mode == CAMPAIGN {
+            game.script = Some(Script::new());
+        }
+        game
+    }
+
+    /// Returns the run's state as text, one field or entity per line
+    pub fn write_state(&self) -> String {
+        let mut body = format!(
+            "seed {}\ntick {}\nrng {}\nscore {}\ntimers {} {}\nbosses {}\nplayer {} {} {} {} {}\n",
+            self.seed,
+            self.tick,
+            self.rng.state(),
+            self.score,
+            self.new_enemy,
+            self.new_boss,
+            self.bosses,
+            self.player.pos.x,
+            self.player.pos.y,
+            self.player.health,
+            self.lives,
+            self.invincible
+        );
+        body.push_str(&format!(
+            "gun {} {} {} {} {}\n",
+            self.gun.weapon().name(),
+            self.gun.level,
+            self.gun.cooldown,
+            self.gun.heat,
+            self.gun.overheated as u8
+        ));
+        body.push_str(&format!("charge {}\n", self.charge));
+        body.push_str(&format!("beam {}\n", self.beam_hold));
+        body.push_str(&format!(
+            "effects {} {} {}\n",
+            self.effects.shield, self.effects.multiplier, self.effects.speed
+        ));
+        body.push_str(&format!("bombs {}\n", self.bombs));
+        body.push_str(&format!(
+            "dash {} {} {}\n",
+            self.dash.facing.0, self.dash.facing.1, self.dash.cooldown
+        ));
+        body.push_str(&format!("drones {}\n", self.drones.count));
+        for pos in &self.drones.history {
+            body.push_str(&format!("history {} {}\n", pos.x, pos.y));
+        }
+        for (pos, time) in &self.dash.trail {
+            body.push_str(&format!("trail {} {} {}\n", pos.x, pos.y, time));
+        }
+        body.push_str(&format!(
+            "combo {} {}\n",
+            self.combo.count, self.combo.timer
+        ));
+        if let Some(wave) = &self.shockwave {
+            body.push_str(&format!(
+                "shockwave {} {} {}\n",
+                wave.centre.x, wave.centre.y, wave.radius
+            ));
+        }
+        for e in &self.enemies {
+            body.push_str(&format!(
+                "enemy {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n",
+                self.defs.get(e.def).name,
+                e.pos.x,
+                e.pos.y,
+                e.velocity.x,
+                e.velocity.y,
+                e.health,
+                e.wait,
+                e.reload,
+                e.volleys,
+                e.moves,
+                e.heading,
+                e.anchor.x,
+                e.anchor.y,
+                e.path.map_or("-", |p| self.defs.path(p).name.as_str()),
+                e.travelled,
+                e.mirrored as u8,
+                e.slot.map_or("-".to_string(), |s| s.to_string()),
+                e.age,
+                e.flash,
+                e.diving as u8
+            ));
+        }
+        if let Some(boss) = &self.boss {
+            let e = &boss.body;
+            body.push_str(&format!(
+                "boss {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+                self.defs.boss(boss.def).name,
+                e.pos.x,
+                e.pos.y,
+                e.velocity.x,
+                e.velocity.y,
+                e.wait,
+                e.reload,
+                e.volleys,
+                e.moves,
+                e.heading,
+                e.anchor.x,
+                e.anchor.y,
+                e.flash,
+                boss.phase
+            ));
+            for health in &boss.parts {
+                body.push_str(&format!(" {}", health));
+            }
+            body.push('\n');
+        }
+        if let Some(script) = &self.script {
+            if script.level < self.defs.level_count() {
+                body.push_str(&format!(
+                    "level {} {} {} {}\n",
+                    self.defs.level(script.level).name,
+                    script.next,
+                    script.wait,
+                    script.repeats
+                ));
+            }
+        }
+        body.push_str(&format!("peel {} {}\n", self.peel, self.peel_timer));
+        if let Some((text, time)) = &self.message {
+            body.push_str(&format!("message {} {}\n", time, text));
+        }
+        for p in &self.popups {
+            body.push_str(&format!(
+                "popup {} {} {} {}\n",
+                p.time, p.pos.x, p.pos.y, p.text
+            ));
+        }
+        for h in &self.hazards {
+            body.push_str(&format!(
+                "hazard {} {} {}\n",
+                h.pos.x,
+                h.pos.y,
+                h.time.map_or("-".to_string(), |t| t.to_string())
+            ));
+        }
+        for p in &self.pickups {
+            body.push_str(&format!(
+                "pickup {} {} {} {}\n",
+                p.kind.name(),
+                p.pos.x,
+                p.pos.y,
+                weapon::WEAPONS[p.weapon].name()
+            ));
+        }
+        for b in &self.bullets {
+            let team = match b.team {
+                Team::Player => "player",
+                Team::Enemy => "enemy",
+            };
+            body.push_str(&format!(
+                "bullet {} {} {} {} {} {} {} {} {}\n",
+                team,
+                b.pos.x,
+                b.pos.y,
+                b.velocity.x,
+                b.velocity.y,
+                b.delay,
+                b.damage,
+                b.pierce,
+                b.grazed as u8
+            ));
+        }
+        body
+    }
+
+    /// Reads a run from text returned by `write_state`
+    ///
+    /// # Arguments
+    /// * body: the text to read
+    /// * defs: the enemy types the run can use
+    pub fn read_state(body: &str, defs: Rc<Registry>) -> io::Result<Self> {
+        let invalid = |line: usize| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("malformed run state at line {}", line + 1),
+            )
+        };
+
+        let mut game = Game::new(0, defs);
+        for (i, line) in body.lines().enumerate() {
+            let mut parts = line.split(' ');
+            let key = parts.next().unwrap_or("");
+            let fields: Vec<&str> = parts.collect();
+
+            // Numbers are read wide enough for any field, then narrowed
+            let num = |index: usize| -> io::Result<i128> {
+                fields[index].parse().map_err(|_| invalid(i))
+            };
+            let vec2 = |index: usize| -> io::Result<Vec2> {
+                match (fields[index].parse(), fields[index + 1].parse()) {
+                    (Ok(x), Ok(y)) if f64::is_finite(x) && f64::is_finite(y) => Ok(Vec2::new(x, y)),
+                    _ => Err(invalid(i)),
+                }
+            };
+            // Players and bullets are always inside the walls
+            let inside = |pos: Vec2| -> io::Result<Vec2> {
+                match game.map.inside(pos.cell()) {
+                    Some(_) => Ok(pos),
+                    None => Err(invalid(i)),
+                }
+            };
+            match (key, fields.len()) {
+                ("seed", 1) => game.seed = num(0)? as u64,
+                ("tick", 1) => game.tick = num(0)? as u64,
+                ("rng", 1) => game.rng = GameRng::from_state(num(0)? as u64),
+                ("score", 1) => game.score = num(0)? as i32,
+                ("timers", 2) => {
+                    game.new_enemy = num(0)? as u128;
+                    game.new_boss = num(1)? as u128;
+                }
+                ("bosses", 1) => game.bosses = num(0)? as u64,
+                ("player", 5) => {
+                    game.player.pos = inside(vec2(0)?)?;
+                    game.player.health = num(2)? as usize;
+                    game.lives = (num(3)? as usize).min(MAX_LIVES);
+                    game.invincible = num(4)? as u128;
+                }
+                ("gun", 5) => {
+                    let weapon = weapon::find(fields[0]).ok_or_else(|| {
+                        io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("unknown weapon {} at line {}", fields[0], i + 1),
+                        )
+                    })?;
+                    game.gun = Gun {
+                        weapon,
+                        level: (num(1)? as usize).clamp(1, weapon::MAX_LEVEL),
+                        cooldown: num(2)? as u128,
+                        heat: (num(3)? as u32).min(weapon::MAX_HEAT),
+                        overheated: num(4)? != 0,
+                    };
+                }
+                ("charge", 1) => {
+                    game.charge = (num(0)? as u128).min(weapon::CHARGE_TIME + CHARGE_TAIL)
+                }
+                ("beam", 1) => game.beam_hold = (num(0)? as u128).min(BEAM_HOLD),
+                ("effects", 3) => {
+                    game.effects = Effects {
+                        shield: num(0)? as u128,
+                        multiplier: num(1)? as u128,
+                        speed: num(2)? as u128,
+                    };
+                }
+                ("combo", 2) => {
+                    game.combo = Combo {
+                        count: num(0)? as usize,
+                        timer: num(1)? as u128,
+                    };
+                }
+                ("popup", 4) => game.popups.push(Popup {
+                    pos: vec2(1)?,
+                    text: fields[3].to_string(),
+                    time: num(0)? as u128,
+                }),
+                ("dash", 3) => {
+                    game.dash.facing = ((num(0)? as i32).signum(), (num(1)? as i32).signum());
+                    game.dash.cooldown = num(2)? as u128;
+                }
+                ("drones", 1) => game.drones.count = (num(0)? as usize).min(MAX_DRONES),
+                ("history", 2) => game.drones.history.push(vec2(0)?),
+                ("trail", 3) => game.dash.trail.push((vec2(0)?, num(2)? as u128)),
+                ("bombs", 1) => game.bombs = (num(0)? as usize).min(MAX_BOMBS),
+                ("shockwave", 3) => match fields[2].parse() {
+                    Ok(radius) if f64::is_finite(radius) => {
+                        game.shockwave = Some(Shockwave {
+                            centre: vec2(0)?,
+                            radius,
+                        })
+                    }
+                    _ => return Err(invalid(i)),
+                },
+                ("pickup", 4) => match (Kind::find(fields[0]), weapon::find(fields[3])) {
+                    (Some(kind), Some(weapon)) => game.pickups.push(Pickup {
+                        pos: vec2(1)?,
+                        kind,
+                        weapon,
+                    }),
+                    _ => return Err(invalid(i)),
+                },
+                ("enemy", 20) => {
+                    // Enemies are saved with the name of their type
+                    let def = game.defs.find(fields[0]).ok_or_else(|| {
+                        io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("unknown enemy type {} at line {}", fields[0], i + 1),
+                        )
+                    })?;
+                    let size = game.defs.get(def).sprite.size();
+                    let mut enemy = Enemy::new(vec2(1)?, size, def, num(5)? as usize);
+                    enemy.velocity = vec2(3)?;
+                    enemy.wait = num(6)? as u128;
+                    enemy.reload = num(7)? as u128;
+                    enemy.volleys = num(8)? as u64;
+                    enemy.moves = num(9)? as u64;
+                    enemy.heading = if num(10)? < 0 { -1 } else { 1 };
+                    enemy.anchor = vec2(11)?;
+                    if fields[13] != "-" {
+                        enemy.path = Some(game.defs.find_path(fields[13]).ok_or_else(|| {
+                            io::Error::new(
+                                ErrorKind::InvalidData,
+                                format!("unknown path {} at line {}", fields[13], i + 1),
+                            )
+                        })?);
+                    }
+                    enemy.travelled = fields[14].parse().map_err(|_| invalid(i))?;
+                    enemy.mirrored = num(15)? != 0;
+                    if fields[16] != "-" {
+                        enemy.slot = Some(num(16)? as usize);
+                    }
+                    enemy.age = num(17)? as u128;
+                    enemy.flash = num(18)? as u128;
+                    enemy.diving = num(19)? != 0;
+                    game.enemies.push(enemy);
+                }
+                ("boss", n) if n >= 14 => {
+                    let index = game.defs.find_boss(fields[0]).ok_or_else(|| {
+                        io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("unknown boss {} at line {}", fields[0], i + 1),
+                        )
+                    })?;
+                    let def = game.defs.boss(index);
+                    if n != 14 + def.parts.len() {
+                        return Err(invalid(i));
+                    }
+                    let mut boss = Boss::new(index, def);
+                    boss.body.pos = vec2(1)?;
+                    boss.body.velocity = vec2(3)?;
+                    boss.body.wait = num(5)? as u128;
+                    boss.body.reload = num(6)? as u128;
+                    boss.body.volleys = num(7)? as u64;
+                    boss.body.moves = num(8)? as u64;
+                    boss.body.heading = if num(9)? < 0 { -1 } else { 1 };
+                    boss.body.anchor = vec2(10)?;
+                    boss.body.flash = num(12)? as u128;
+                    boss.phase = (num(13)? as usize).min(def.phases.len() - 1);
+                    for (part, health) in boss.parts.iter_mut().enumerate() {
+                        *health = (num(14 + part)? as usize).min(def.parts[part].health);
+                    }
+                    boss.body.health = boss.parts.iter().sum();
+                    game.boss = Some(boss);
+                }
+                ("level", 4) => {
+                    let level = game.defs.find_level(fields[0]).ok_or_else(|| {
+                        io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("unknown level {} at line {}", fields[0], i + 1),
+                        )
+                    })?;
+                    game.script = Some(Script {
+                        level,
+                        next: (num(1)? as usize).min(game.defs.level(level).events.len()),
+                        wait: num(2)? as u128,
+                        repeats: num(3)? as u64,
+                    });
+                }
+                ("peel", 2) => {
+                    game.peel = num(0)? as u128;
+                    game.peel_timer = num(1)? as u128;
+                }
+                ("message", n) if n >= 2 => {
+                    game.message = Some((fields[1..].join(" "), num(0)? as u128));
+                }
+                ("hazard", 3) => {
+                    let time = match fields[2] {
+                        "-" => None,
+                        _ => Some(num(2)? as u128),
+                    };
+                    game.hazards.push(Hazard {
+                        pos: inside(vec2(0)?)?,
+                        time,
+                    });
+                }
+                ("bullet", 9) => {
+                    let team = match fields[0] {
+                        "player" => Team::Player,
+                        "enemy" => Team::Enemy,
+                        _ => return Err(invalid(i)),
+                    };
+                    let mut bullet = Bullet::new(inside(vec2(1)?)?, BULLET_SYM, team, vec2(3)?);
+                    bullet.delay = num(5)? as u128;
+                    bullet.damage = num(6)? as usize;
+                    bullet.pierce = num(7)? as usize;
+                    bullet.grazed = num(8)? != 0;
+                    bullet.symbol = match team {
+                        Team::Player => glyph(bullet.velocity),
+                        Team::Enemy => enemy_glyph(&bullet),
+                    };
+                    game.bullets.push(bullet);
+                }
+                _ => return Err(invalid(i)),
+            }
+        }
+        game.redraw();
+        Ok(game)
+    }
+
+    /// Returns true once the player has run out of health
+    pub fn is_over(&self) -> bool {
+        self.player.health == 0 || self.cleared()
+    }
+
+    /// Returns true if the run played through every level
+    pub fn cleared(&self) -> bool {
+        self.script
+            .as_ref()
+            .is_some_and(|s| s.level >= self.defs.level_count())
+    }
+
+    /// Returns the name of the boss being fought, its health and its full health
+    pub fn boss_bar(&self) -> Option<(&str, usize, usize)> {
+        self.boss.as_ref().map(|boss| {
+            let def = self.defs.boss(boss.def);
+            (def.name.as_str(), boss.body.health, def.health())
+        })
+    }
+
+    /// Damages the enemy or boss part in a cell, if there is one
+    ///
+    /// A boss's armour stops bullets without being damaged. Enemies are worth
+    /// more if they are destroyed quickly or with damage to spare.
+    ///
+    /// # Arguments
+    /// * pos: the cell that was shot
+    /// * damage: the health to take
+    fn shoot(&mut self, pos: (i32, i32), damage: usize) -> Hit {
+        if let Some(boss) = self.boss.as_mut() {
+            let def = self.defs.boss(boss.def);
+            if let Some(part) = boss.part_at(def, pos) {
+                boss.body.flash = HIT_FLASH;
+                if boss.damage(def, part, damage) {
+                    let pos = boss.body.pos;
+                    self.boss = None;
+                    self.combo.kill();
+                    let points = self.award(def.score);
+                    self.popup(pos, points);
+                }
+                return Hit::Stopped;
+            }
+            if boss.covers(def, pos) {
+                return Hit::Stopped;
+            }
+        }
+
+        let index = match self.enemies.iter().position(|e| e.covers(pos)) {
+            Some(index) => index,
+            None => {
+                // Mines can be shot, but other hazards let bullets through
+                let mine = self
+                    .hazards
+                    .iter()
+                    .position(|h| h.is_mine() && h.pos.cell() == pos);
+                return match mine {
+                    Some(mine) => {
+                        self.hazards.remove(mine);
+                        Hit::Destroyed
+                    }
+                    None => Hit::Miss,
+                };
+            }
+        };
+        let enemy = &mut self.enemies[index];
+        enemy.flash = HIT_FLASH;
+        if damage < enemy.health {
+            enemy.health -= damage;
+            return Hit::Stopped;
+        }
+
+        let score = self.defs.get(enemy.def).score;
+        let mut points = score + (damage - enemy.health) as i32 * OVERKILL_BONUS;
+        if enemy.age <= FAST_KILL {
+            points += score * FAST_KILL_BONUS / 100;
+        }
+        let pos = enemy.pos;
+        self.combo.kill();
+        let points = self.award(points);
+        self.popup(pos, points);
+        self.destroy(index);
+        Hit::Destroyed
+    }
+
+    /// Returns the milliseconds of charge letting go of fire would use
+    ///
+    /// A tap only counts as held for `CHARGE_TAIL`, which leaves nothing once
+    /// that is taken off.
+    pub fn held_charge(&self) -> u128 {
+        self.charge.saturating_sub(CHARGE_TAIL)
+    }
+
+    /// Returns what points scored are multiplied by, from the combo and any
+    /// multiplier pickup
+    pub fn multiplier(&self) -> i32 {
+        let pickup = if self.effects.multiplier > 0 {
+            MULTIPLIER
+        } else {
+            1
+        };
+        self.combo.multiplier() * pickup
+    }
+
+    /// Adds points to the score, multiplied by `multiplier`, and gives the
+    /// player a bomb for each multiple of `BOMB_SCORE` passed and a life for
+    /// each multiple of `LIFE_SCORE`, returning the points added
+    ///
+    /// # Arguments
+    /// * points: the points scored
+    fn award(&mut self, points: i32) -> i32 {
+        let before = self.score;
+        let points = points * self.multiplier();
+        self.score += points;
+        let earned = (self.score / BOMB_SCORE - before / BOMB_SCORE).max(0) as usize;
+        self.bombs = (self.bombs + earned).min(MAX_BOMBS);
+        let earned = (self.score / LIFE_SCORE - before / LIFE_SCORE).max(0) as usize;
+        self.lives = (self.lives + earned).min(MAX_LIVES);
+        points
+    }
+
+    /// Shows points scored floating up from where they were earned
+    ///
+    /// # Arguments
+    /// * pos: where the points were earned
+    /// * points: the points scored
+    fn popup(&mut self, pos: Vec2, points: i32) {
+        self.popups.push(Popup {
+            pos,
+            text: format!("+{}", points),
+            time: POPUP_TIME,
+        });
+    }
+
+    /// Scores the enemy bullets passing right next to the player, each only
+    /// the first time it does
+    fn graze(&mut self) {
+        let (x, y) = self.player.pos.cell();
+        let mut grazes = 0;
+        for b in &mut self.bullets {
+            let (bx, by) = b.pos.cell();
+            let close = (bx - x).abs() <= 1 && (by - y).abs() <= 1 && (bx, by) != (x, y);
+            if b.team == Team::Enemy && !b.grazed && close {
+                b.grazed = true;
+                grazes += 1;
+            }
+        }
+        if grazes > 0 {
+            self.award(grazes * GRAZE_POINTS);
+        }
+    }
+
+    /// Hits the player, unless a shield absorbs the hit or they are still
+    /// invincible from the last one, losing one of their drones
+    ///
+    /// A hit that would lose a life sets off a bomb instead, if one can be.
+    ///
+    /// # Arguments
+    /// * damage: the health to take
+    fn hurt(&mut self, damage: usize) {
+        if self.effects.shield > 0 || self.invincible > 0 || damage == 0 {
+            return;
+        }
+        self.invincible = INVINCIBLE_TIME;
+        self.drones.count = self.drones.count.saturating_sub(1);
+        if damage >= self.player.health && self.detonate() {
+            return;
+        }
+        self.wound(damage);
+    }
+
+    /// Takes health from the player, losing a life if it runs out
+    ///
+    /// The player respawns at the bottom of the map with full health and a
+    /// brief shield, until the last life is lost.
+    ///
+    /// # Arguments
+    /// * damage: the health to take
+    fn wound(&mut self, damage: usize) {
+        self.player.health = self.player.health.saturating_sub(damage);
+        if self.player.health > 0 || self.lives == 0 {
+            return;
+        }
+        self.lives -= 1;
+        if self.lives > 0 {
+            self.player.health = MAX_HEALTH;
+            self.player.pos = Vec2::new((WIDTH / 2) as f64, (HEIGHT - 2) as f64);
+            self.effects.shield = self.effects.shield.max(RESPAWN_SHIELD);
+        }
+    }
+
+    /// Gives the player what a pickup holds
+    ///
+    /// # Arguments
+    /// * pickup: the pickup collected
+    fn collect(&mut self, pickup: &Pickup) {
+        match pickup.kind {
+            Kind::Weapon => self.gun.pick_up(pickup.weapon),
+            Kind::Heal => {
+                // Health past full is an overshield, up to a limit
+                let health = self.player.health;
+                self.player.health = (health + 1).min(health.max(MAX_HEALTH + OVERSHIELD));
+            }
+            Kind::Shield => self.effects.shield = SHIELD_TIME,
+            Kind::Bomb => self.bombs = (self.bombs + 1).min(MAX_BOMBS),
+            Kind::Multiplier => self.effects.multiplier = MULTIPLIER_TIME,
+            Kind::Speed => self.effects.speed = SPEED_TIME,
+            Kind::Drone => self.drones.count = (self.drones.count + 1).min(MAX_DRONES),
+        }
+    }
+
+    /// Fires a plain bullet from each of the player's drones
+    fn fire_drones(&mut self) {
+        for pos in self.drones.positions(self.player.pos, self.focused) {
+            self.launch(pos, &Shot::new(0, 0.0));
+        }
+    }
+
+    /// Fills the column above the player with their beam, up to the first
+    /// enemy, boss or mine in the way, damaging it whenever the weapon is
+    /// ready, and returns the cells the beam filled
+    fn fire_beam(&mut self) -> Vec<(usize, usize)> {
+        if self.gun.overheated {
+            return Vec::new();
+        }
+        let (x, y) = self.player.pos.cell();
+        let ray = self.map.raycast((x, y - 1), (0, -1), |cell| {
+            let boss = self
+                .boss
+                .as_ref()
+                .is_some_and(|b| b.covers(self.defs.boss(b.def), cell));
+            let mine = self
+                .hazards
+                .iter()
+                .any(|h| h.is_mine() && h.pos.cell() == cell);
+            boss || mine || self.enemies.iter().any(|e| e.covers(cell))
+        });
+        if let Some(shots) = self.gun.fire() {
+            self.fire_drones();
+            if let Some(hit) = ray.hit {
+                for shot in shots {
+                    self.shoot(hit, shot.damage);
+                }
+            }
+        }
+        ray.cells
+    }
+
+    /// Fires one bullet from in front of the player or one of their drones
+    ///
+    /// # Arguments
+    /// * from: where the player or drone is
+    /// * shot: the bullet to fire
+    fn launch(&mut self, from: Vec2, shot: &Shot) {
+        let pos = from + Vec2::new(shot.offset as f64, -1.0);
+        if self.map.inside(pos.cell()).is_none() {
+            return;
+        }
+        // Anything right in front of the ship is hit straight away
+        let mut pierce = shot.pierce;
+        match self.shoot(pos.cell(), shot.damage) {
+            Hit::Miss => (),
+            Hit::Destroyed if pierce > 0 => pierce -= 1,
+            _ => return,
+        }
+        let velocity = shot.direction() * (1000.0 / BULLET_SPD as f64);
+        let mut bullet = Bullet::new(pos, glyph(velocity), Team::Player, velocity);
+        bullet.damage = shot.damage;
+        bullet.pierce = pierce;
+        self.bullets.push(bullet);
+    }
+
+    /// Dashes the player a few cells the way they last moved, stopping at
+    /// the walls, and makes them briefly invincible
+    fn dodge(&mut self) {
+        if !self.dash.ready() {
+            return;
+        }
+        let (dx, dy) = self.dash.facing;
+        let mut passed = Vec::new();
+        for _ in 0..DASH_DISTANCE {
+            let next = self.player.pos + Vec2::new(dx as f64, dy as f64);
+            if self.map.inside(next.cell()).is_none() {
+                break;
+            }
+            passed.push(self.player.pos);
+            self.player.pos = next;
+        }
+        // Dashing into a wall goes nowhere, so it doesn't use up the dash
+        if !passed.is_empty() {
+            self.dash.start(passed);
+            self.invincible = self.invincible.max(DASH_INVINCIBLE);
+        }
+    }
+
+    /// Sets off a bomb where the player is, starting a new shockwave
+    ///
+    /// Returns false, using up nothing, if the player has no bombs or the
+    /// last one's shockwave is still spreading.
+    fn detonate(&mut self) -> bool {
+        if self.bombs == 0 || self.shockwave.is_some() {
+            return false;
+        }
+        self.bombs -= 1;
+        self.shockwave = Some(Shockwave::new(self.player.pos));
+        true
+    }
+
+    /// Spreads the shockwave, clearing the enemy bullets inside it and
+    /// damaging each enemy and boss part on the map as the ring reaches its
+    /// middle
+    fn step_shockwave(&mut self) {
+        let wave = match self.shockwave.as_mut() {
+            Some(wave) => wave,
+            None => return,
+        };
+        let reached = wave.radius;
+        wave.radius += SHOCKWAVE_SPEED * TICK_MS as f64 / 1000.0;
+        let wave = wave.clone();
+
+        self.bullets
+            .retain(|b| b.team == Team::Player || wave.distance(b.pos) > wave.radius);
+        let passes = |corner: Vec2, size: (usize, usize)| {
+            let distance =
+                wave.distance(corner + Vec2::new((size.0 / 2) as f64, (size.1 / 2) as f64));
+            distance >= reached && distance < wave.radius
+        };
+        let mut cells: Vec<(i32, i32)> = self
+            .enemies
+            .iter()
+            .filter(|e| passes(e.pos, e.size))
+            .map(|e| e.pos.cell())
+            .collect();
+        if let Some(boss) = &self.boss {
+            let def = self.defs.boss(boss.def);
+            for (i, part) in def.parts.iter().enumerate() {
+                let corner = boss.body.pos + Vec2::new(part.pos.0 as f64, part.pos.1 as f64);
+                if boss.parts[i] > 0 && passes(corner, part.size) {
+                    cells.push(corner.cell());
+                }
+            }
+        }
+        cells.retain(|cell| self.map.inside(*cell).is_some());
+        for cell in cells {
+            self.shoot(cell, BOMB_DAMAGE);
+        }
+
+        if wave.is_done((WIDTH, HEIGHT)) {
+            self.shockwave = None;
+        }
+    }
+
+    /// Removes a destroyed enemy, leaving behind whatever its type does
+    ///
+    /// # Arguments
+    /// * index: the index of the enemy
+    fn destroy(&mut self, index: usize) {
+        let e = self.enemies.remove(index);
+        let defs = self.defs.clone();
+        let def = defs.get(e.def);
+        let middle = e.pos + Vec2::new((e.size.0 / 2) as f64, (e.size.1 / 2) as f64);
+
+        // Roll the type's drop table, with no pickup at all for the rest
+        let mut roll = self.rng.gen_range(0, 100);
+        for drop in &def.drops {
+            if roll < drop.chance {
+                self.pickups.push(Pickup {
+                    pos: middle.snap(),
+                    kind: drop.kind,
+                    weapon: self.rng.gen_range(0, weapon::WEAPONS.len()),
+                });
+                break;
+            }
+            roll -= drop.chance;
+        }
+
+        match &def.on_death {
+            None => (),
+            Some(OnDeath::Split { child, count }) => {
+                let child = match defs.find(child) {
+                    Some(child) => child,
+                    None => return,
+                };
+                // The children spread out from the parent's middle, inside the walls
+                let width = defs.get(child).sprite.size().0;
+                let span = count * (width + 1) - 1;
+                let right = (WIDTH - 1).saturating_sub(span).max(1);
+                let left = (middle.x - (span / 2) as f64).clamp(1.0, right as f64);
+                for i in 0..*count {
+                    let pos = Vec2::new(left + (i * (width + 1)) as f64, e.pos.y).snap();
+                    self.spawn(child, pos);
+                    if let Some(spawned) = self.enemies.last_mut() {
+                        spawned.heading = if i * 2 + 1 < *count { -1 } else { 1 };
+                    }
+                }
+            }
+            Some(OnDeath::Explode { pattern, damage }) => fire(
+                &self.map,
+                &mut self.bullets,
+                pattern,
+                *damage,
+                0,
+                middle,
+                self.player.pos,
+            ),
+            Some(OnDeath::Mine) if self.map.inside(middle.cell()).is_some() => {
+                self.hazards.push(Hazard {
+                    pos: middle.snap(),
+                    time: None,
+                });
+            }
+            Some(OnDeath::Mine) => (),
+            Some(OnDeath::Hazard(time)) => {
+                // Every cell the enemy covered burns for a while
+                let (left, top) = e.pos.cell();
+                for (dy, row) in def.sprite.rows.iter().enumerate() {
+                    for (dx, c) in row.iter().enumerate() {
+                        let cell = (left + dx as i32, top + dy as i32);
+                        if *c != ' ' && self.map.inside(cell).is_some() {
+                            self.hazards.push(Hazard {
+                                pos: Vec2::new(cell.0 as f64, cell.1 as f64),
+                                time: Some(*time),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns a layer showing the enemies and boss that were just hit in
+    /// inverted colours
+    pub fn flash_layer(&self) -> Layer {
+        let mut layer = Layer::new(Attribute::Reverse);
+        let boss = self
+            .boss
+            .iter()
+            .map(|b| (&b.body, b.sprite(self.defs.boss(b.def))));
+        let enemies = self
+            .enemies
+            .iter()
+            .map(|e| (e, self.defs.get(e.def).sprite_for(e.health).clone()));
+        for (e, sprite) in enemies.chain(boss).filter(|(e, _)| e.flash > 0) {
+            let (left, top) = e.pos.cell();
+            for (dy, row) in sprite.rows.iter().enumerate() {
+                for (dx, c) in row.iter().enumerate() {
+                    if let Some(pos) = self.map.inside((left + dx as i32, top + dy as i32)) {
+                        if *c != ' ' {
+                            layer.set(pos, self.map.get(pos));
+                        }
+                    }
+                }
+            }
+        }
+        layer
+    }
+
+    /// Returns the ring of the spreading shockwave, if a bomb has gone off
+    pub fn shockwave_layer(&self) -> Layer {
+        let mut layer = Layer::new(Attribute::Bold);
+        if let Some(wave) = &self.shockwave {
+            for pos in wave.ring((WIDTH, HEIGHT)) {
+                layer.set(pos, SHOCKWAVE_SYM);
+            }
+        }
+        layer
+    }
+
+    /// Returns the after-images left by the player's last dash
+    pub fn trail_layer(&self) -> Layer {
+        let mut layer = Layer::new(Attribute::Dim);
+        for (pos, _) in &self.dash.trail {
+            if let Some(pos) = self.map.inside(pos.cell()) {
+                layer.set(pos, self.player.symbol);
+            }
+        }
+        layer
+    }
+
+    /// Returns true if the player is hidden this tick, as they blink while
+    /// invincible
+    fn blinked_out(&self) -> bool {
+        (self.invincible / BLINK) % 2 == 1
+    }
+
+    /// Returns the player's hitbox while they are focused, so they can see
+    /// exactly which cell bullets have to miss
+    pub fn hitbox_layer(&self) -> Layer {
+        let mut layer = Layer::new(Attribute::Reverse);
+        if let Some(pos) = self.map.inside(self.player.pos.cell()) {
+            if self.focused && !self.blinked_out() {
+                layer.set(pos, self.player.symbol);
+            }
+        }
+        layer
+    }
+
+    /// Returns true if an enemy is so far off the map that it will not return
+    ///
+    /// Enemies can be a whole map's height above the top while they fly in,
+    /// and anywhere while they follow an entry path.
+    ///
+    /// # Arguments
+    /// * e: the enemy to check
+    fn off_map(e: &Enemy) -> bool {
+        if e.path.is_some() {
+            return false;
+        }
+        let (x, y) = e.pos.cell();
+        let (w, h) = (e.size.0 as i32, e.size.1 as i32);
+        x + w <= 0 || x >= WIDTH as i32 || y >= HEIGHT as i32 || y + h <= -(HEIGHT as i32)
+    }
+
+    /// Redraws the map from the entities in play, snapping each to its cell
+    fn redraw(&mut self) {
+        self.map.clear();
+        for h in &self.hazards {
+            if let Some(pos) = self.map.inside(h.pos.cell()) {
+                self.map
+                    .set(pos, if h.is_mine() { MINE_SYM } else { HAZARD_SYM });
+            }
+        }
+        for b in &self.bullets {
+            if let Some(pos) = self.map.inside(b.pos.cell()) {
+                self.map.set(pos, b.symbol);
+            }
+        }
+        for pos in &self.beam {
+            self.map.set(*pos, BEAM_SYM);
+        }
+        for p in &self.pickups {
+            if let Some(pos) = self.map.inside(p.pos.cell()) {
+                self.map.set(pos, p.kind.symbol());
+            }
+        }
+        for e in &self.enemies {
+            self.map
+                .draw_sprite(e.pos.cell(), self.defs.get(e.def).sprite_for(e.health));
+        }
+        if let Some(boss) = &self.boss {
+            let sprite = boss.sprite(self.defs.boss(boss.def));
+            self.map.draw_sprite(boss.body.pos.cell(), &sprite);
+        }
+        for p in &self.popups {
+            let (x, y) = p.pos.cell();
+            for (i, c) in p.text.chars().enumerate() {
+                if let Some(pos) = self.map.inside((x + i as i32, y)) {
+                    self.map.set(pos, c);
+                }
+            }
+        }
+        if let Some((text, _)) = &self.message {
+            let x = (WIDTH as i32 - text.chars().count() as i32) / 2;
+            for (i, c) in text.chars().enumerate() {
+                if let Some(pos) = self.map.inside((x + i as i32, (HEIGHT / 3) as i32)) {
+                    self.map.set(pos, c);
+                }
+            }
+        }
+        for d in self.drones.positions(self.player.pos, self.focused) {
+            if let Some(pos) = self.map.inside(d.cell()) {
+                self.map.set(pos, DRONE_SYM);
+            }
+        }
+        if let Some(pos) = self.map.inside(self.player.pos.cell()) {
+            if !self.blinked_out() {
+                self.map.set(pos, self.player.symbol);
+            }
+        }
+    }
+
+    /// Adds an enemy, heading towards the middle of the map
+    ///
+    /// # Arguments
+    /// * def: the index of the enemy's type
+    /// * pos: the enemy's upper-left corner
+    fn spawn(&mut self, def: usize, pos: Vec2) {
+        let (size, health) = (self.defs.get(def).sprite.size(), self.defs.get(def).health);
+        let mut enemy = Enemy::new(pos, size, def, health);
+        if pos.x > (WIDTH / 2) as f64 {
+            enemy.heading = -1;
+        }
+        self.enemies.push(enemy);
+    }
+
+    /// Adds a group of enemies laid out in a formation
+    ///
+    /// # Arguments
+    /// * def: the index of the enemies' type
+    /// * x: the left column of the group, or None for a random one
+    /// * formation: how the group is laid out
+    /// * count: the number of enemies
+    fn spawn_group(&mut self, def: usize, x: Option<usize>, formation: Formation, count: usize) {
+        let (width, height) = self.defs.get(def).sprite.size();
+        let span = match formation {
+            Formation::Column => width,
+            _ => count * (width + 1) - 1,
+        };
+        let x = x.unwrap_or_else(|| self.rng.gen_range(1, WIDTH - span));
+        for i in 0..count {
+            // Enemies that do not fit at the top wait above the map
+            let (dx, dy) = match formation {
+                Formation::Row => (i * (width + 1), 0),
+                Formation::Column => (0, i * (height + 1)),
+                Formation::Vee => (i * (width + 1), (count / 2).abs_diff(i)),
+            };
+            self.spawn(def, Vec2::new((x + dx) as f64, 1.0 - dy as f64));
+        }
+    }
+
+    /// Brings in a boss
+    ///
+    /// # Arguments
+    /// * index: the index of the boss
+    fn start_boss(&mut self, index: usize) {
+        self.boss = Some(Boss::new(index, self.defs.boss(index)));
+        self.bosses += 1;
+    }
+
+    /// Runs the level script up to its next wait
+    ///
+    /// A level ends once its script has run out and everything in it is
+    /// destroyed, and the next level starts straight away.
+    fn step_script(&mut self) {
+        let mut script = match self.script.take() {
+            Some(script) => script,
+            None => return,
+        };
+        let defs = self.defs.clone();
+        script.wait = script.wait.saturating_sub(TICK_MS);
+        let clear = |game: &Game| game.enemies.is_empty() && game.boss.is_none();
+        while script.wait == 0 && script.level < defs.level_count() {
+            let level = defs.level(script.level);
+            let event = match level.events.get(script.next) {
+                Some(event) => event,
+                None => {
+                    if !clear(self) {
+                        break;
+                    }
+                    script = Script {
+                        level: script.level + 1,
+                        ..Script::new()
+                    };
+                    self.peel = 0;
+                    continue;
+                }
+            };
+            script.next += 1;
+            match event {
+                Event::Spawn {
+                    def,
+                    x,
+                    formation,
+                    count,
+                } => self.spawn_group(*def, *x, *formation, *count),
+                Event::Wait(time) => script.wait = *time,
+                Event::Clear => {
+                    if !clear(self) {
+                        script.next -= 1;
+                        break;
+                    }
+                }
+                Event::Message(text) => self.message = Some((text.clone(), MESSAGE_TIME)),
+                Event::Boss(index) => self.start_boss(*index),
+                Event::Enter {
+                    def,
+                    path,
+                    mirrored,
+                } => {
+                    let size = defs.get(*def).sprite.size();
+                    let start = defs.path(*path).at(0.0, *mirrored, size.0);
+                    let mut enemy =
+                        Enemy::new(start.unwrap_or_default(), size, *def, defs.get(*def).health);
+                    enemy.path = Some(*path);
+                    enemy.mirrored = *mirrored;
+                    enemy.slot = formation::open_slot(&self.enemies);
+                    self.enemies.push(enemy);
+                }
+                Event::Peel(time) => {
+                    self.peel = *time;
+                    self.peel_timer = 0;
+                }
+                Event::Mark => (),
+                Event::Loop { start, count } => {
+                    if count.is_none_or(|count| script.repeats < count) {
+                        script.repeats += 1;
+                        script.next = *start;
+                        // Going back carries on next tick, so a loop that only
+                        // clears can't hold up the run when nothing is left
+                        break;
+                    } else {
+                        script.repeats = 0;
+                    }
+                }
+            }
+        }
+        self.script = Some(script);
+    }
+
+    /// Moves the boss, if there is one, and fires its weapon from each part
+    /// still standing
+    fn step_boss(&mut self) {
+        let boss = match self.boss.as_mut() {
+            Some(boss) => boss,
+            None => return,
+        };
+        let def = self.defs.boss(boss.def);
+        let phase = &def.phases[boss.phase];
+        let arrived = boss.arrived();
+
+        let body = &mut boss.body;
+        body.flash = body.flash.saturating_sub(TICK_MS);
+        body.wait += TICK_MS;
+        if body.wait >= phase.speed {
+            body.wait = 0;
+            body.pos = body.pos.snap();
+            // Fly in from above the map before starting the phase's movement
+            let mut next = if arrived {
+                let next = phase.movement.next(body, self.player.pos, &self.map);
+                body.moves += 1;
+                next
+            } else {
+                body.pos + Vec2::new(0.0, 1.0)
+            };
+            // Bosses stay in the top half of the map
+            next.y = next.y.min((HEIGHT / 2 - body.size.1) as f64);
+            body.velocity = (next - body.pos) * (1000.0 / phase.speed as f64);
+        }
+        body.pos += body.velocity * (TICK_MS as f64 / 1000.0);
+
+        let weapon = match &phase.weapon {
+            Some(weapon) if arrived => weapon,
+            _ => return,
+        };
+        body.reload += TICK_MS;
+        if body.reload < weapon.interval {
+            return;
+        }
+        body.reload = 0;
+        let volley = body.volleys;
+        body.volleys += 1;
+        for muzzle in boss.muzzles(def) {
+            fire(
+                &self.map,
+                &mut self.bullets,
+                &weapon.pattern,
+                weapon.damage,
+                volley,
+                muzzle,
+                self.player.pos,
+            );
+        }
+    }
+
+    /// Moves pickups down the map, or towards the player once they are close,
+    /// and collects any the player reaches
+    fn step_pickups(&mut self) {
+        let secs = TICK_MS as f64 / 1000.0;
+        let player = self.player.pos;
+        for p in &mut self.pickups {
+            let offset = player - p.pos;
+            let step = MAGNET_SPEED * secs;
+            if offset.length() <= step {
+                p.pos = player;
+            } else if offset.length() <= MAGNET_RADIUS {
+                p.pos += offset * (step / offset.length());
+            } else {
+                p.pos.y += PICKUP_SPEED * secs;
+            }
+        }
+        self.pickups.retain(|p| p.pos.cell().1 < HEIGHT as i32 - 1);
+
+        let cell = player.cell();
+        let (collected, left): (Vec<Pickup>, Vec<Pickup>) = std::mem::take(&mut self.pickups)
+            .into_iter()
+            .partition(|p| p.pos.cell() == cell);
+        self.pickups = left;
+        for pickup in &collected {
+            self.collect(pickup);
+        }
+    }
+
+    /// Advances the run by one tick
+    ///
+    /// # Arguments
+    /// * commands: the player's inputs for this tick, in order
+    pub fn step(&mut self, commands: &[Command]) {
+        // Regular spawning waits while a boss is being fought
+        if self.boss.is_none() {
+            self.new_enemy += TICK_MS;
+            self.new_boss += TICK_MS;
+        }
+        let secs = TICK_MS as f64 / 1000.0;
+        self.gun.cool(TICK_MS);
+        self.effects.tick(TICK_MS);
+        self.combo.tick(TICK_MS);
+        self.dash.tick(TICK_MS);
+        self.invincible = self.invincible.saturating_sub(TICK_MS);
+
+        let player = &mut self.player;
+
+        // Hazards burn out, but mines stay until they are set off
+        for h in &mut self.hazards {
+            h.time = h.time.map(|t| t.saturating_sub(TICK_MS));
+        }
+        self.hazards.retain(|h| h.time != Some(0));
+
+        // Move existing enemies, each at the speed of its type, noting which
+        // are destroyed rather than just gone
+        let mut to_remove: Vec<(usize, bool)> = Vec::new();
+        let mut leaked = 0;
+        for (i, e) in self.enemies.iter_mut().enumerate() {
+            let def = self.defs.get(e.def);
+            e.age += TICK_MS;
+            e.flash = e.flash.saturating_sub(TICK_MS);
+            if let Some(index) = e.path {
+                // Fly in along the entry path
+                let path = self.defs.path(index);
+                e.travelled += path.speed * secs;
+                match path.at(e.travelled, e.mirrored, e.size.0) {
+                    Some(point) => e.pos = point,
+                    None => e.path = None,
+                }
+            } else if let Some(slot) = e.slot {
+                // Glide into the formation and wait there to peel off
+                let offset = formation::slot_position(slot) - e.pos;
+                let step = SETTLE_SPEED * secs;
+                if offset.length() <= step {
+                    e.pos = formation::slot_position(slot);
+                } else {
+                    e.pos += offset * (step / offset.length());
+                }
+            } else {
+                // Enemies that peeled off a formation dive, whatever their type
+                let (movement, speed) = if e.diving {
+                    (&PEEL_DIVE as &dyn Movement, PEEL_SPEED)
+                } else {
+                    (def.movement.as_ref(), def.speed)
+                };
+                e.wait += TICK_MS;
+                if e.wait >= speed {
+                    e.wait = 0;
+
+                    // Pick the next point to glide to, arriving just as the timer is up
+                    e.pos = e.pos.snap();
+                    let next = movement.next(e, player.pos, &self.map);
+                    e.moves += 1;
+                    if next.y + e.size.1 as f64 >= HEIGHT as f64 {
+                        // If it reached the bottom
+                        leaked += 1;
+                        to_remove.push((i, false));
+                        continue;
+                    }
+                    e.velocity = (next - e.pos) * (1000.0 / speed as f64);
+                }
+                e.pos += e.velocity * secs;
+            }
+
+            // Check if ran into the player
+            if e.covers(player.pos.cell()) {
+                to_remove.push((i, true));
+            } else if Game::off_map(e) {
+                to_remove.push((i, false));
+            }
+        }
+        // Remove any enemies no longer in play
+        for (i, destroyed) in to_remove.into_iter().rev() {
+            if destroyed {
+                self.hurt(1);
+                self.destroy(i);
+            } else {
+                self.enemies.remove(i);
+            }
+        }
+        // Enemies getting past cost health, whether or not the player is invincible
+        self.wound(leaked);
+
+        // Every so often, one enemy waiting in the formation peels off to attack
+        if self.peel > 0 {
+            self.peel_timer += TICK_MS;
+            if self.peel_timer >= self.peel {
+                self.peel_timer = 0;
+                let waiting: Vec<usize> = (0..self.enemies.len())
+                    .filter(|i| formation::settled(&self.enemies[*i]))
+                    .collect();
+                if !waiting.is_empty() {
+                    let e = &mut self.enemies[waiting[self.rng.gen_range(0, waiting.len())]];
+                    e.slot = None;
+                    e.moves = 0;
+                    e.wait = 0;
+                    e.diving = true;
+                }
+            }
+        }
+
+        // Enemies with weapons fire from just below their middle
+        for e in &mut self.enemies {
+            let weapon = match &self.defs.get(e.def).weapon {
+                Some(weapon) => weapon,
+                None => continue,
+            };
+            e.reload += TICK_MS;
+            if e.reload < weapon.interval {
+                continue;
+            }
+            e.reload = 0;
+
+            let muzzle = e.pos + Vec2::new((e.size.0 / 2) as f64, e.size.1 as f64);
+            fire(
+                &self.map,
+                &mut self.bullets,
+                &weapon.pattern,
+                weapon.damage,
+                e.volleys,
+                muzzle,
+                self.player.pos,
+            );
+            e.volleys += 1;
+        }
+        self.step_boss();
+
+        // Move existing bullets, stopping at the first thing they hit
+        let mut bullets = std::mem::take(&mut self.bullets);
+        bullets.retain_mut(|b| {
+            // Delayed bullets hang in place until they are ready
+            if b.delay > 0 {
+                b.delay = b.delay.saturating_sub(TICK_MS);
+                b.symbol = enemy_glyph(b);
+                return true;
+            }
+            for point in b.path(TICK_MS) {
+                b.pos = point;
+                // Bullets leaving the map are gone for good
+                if self.map.inside(point.cell()).is_none() {
+                    return false;
+                }
+                match b.team {
+                    Team::Player => match self.shoot(point.cell(), b.damage) {
+                        Hit::Miss => (),
+                        // Piercing bullets carry on through what they destroy
+                        Hit::Destroyed if b.pierce > 0 => b.pierce -= 1,
+                        _ => return false,
+                    },
+                    Team::Enemy if self.player.pos.cell() == point.cell() => {
+                        self.hurt(b.damage);
+                        return false;
+                    }
+                    Team::Enemy => (),
+                }
+            }
+            true
+        });
+        // Keep any bullets fired by enemies destroyed along the way
+        bullets.append(&mut self.bullets);
+        self.bullets = bullets;
+
+        if self.script.is_some() {
+            // Levels bring in enemies as their script says
+            self.step_script();
+        } else {
+            // Bring in the next boss every BOSS_GEN_SPD milliseconds of regular play
+            if self.new_boss > BOSS_GEN_SPD && self.defs.boss_count() > 0 {
+                let index = self.bosses as usize % self.defs.boss_count();
+                self.start_boss(index);
+                self.new_boss = 0;
+            }
+
+            // Generate an enemy every ENEMY_GEN_SPD milliseconds
+            if self.boss.is_none() && self.new_enemy > ENEMY_GEN_SPD {
+                let def = self.defs.pick(&mut self.rng);
+                let width = self.defs.get(def).sprite.size().0;
+                let x = self.rng.gen_range(1, WIDTH - width);
+                self.spawn(def, Vec2::new(x as f64, 1.0));
+                self.new_enemy = 0;
+            }
+        }
+        if let Some((_, time)) = &mut self.message {
+            *time = time.saturating_sub(TICK_MS);
+            if *time == 0 {
+                self.message = None;
+            }
+        }
+        for p in &mut self.popups {
+            p.time = p.time.saturating_sub(TICK_MS);
+            p.pos.y -= POPUP_SPEED * secs;
+        }
+        self.popups.retain(|p| p.time > 0);
+
+        // WASD to move player
+        // Up to shoot
+        // Focusing slows the player and narrows their weapon's spread
+        self.focused = commands.contains(&Command::Focus);
+        let mut stride = if self.effects.speed > 0 { 2.0 } else { 1.0 };
+        if self.focused {
+            stride *= FOCUS_STRIDE;
+        }
+        let moves: Vec<(i32, i32)> = commands.iter().filter_map(|c| c.direction()).collect();
+        self.dash.face(&moves);
+        // A beam stays on while the fire key repeats. Fire counts as held for
+        // far longer than that, so going by it would leave a tap's beam on
+        if commands.contains(&Command::Fire) {
+            self.beam_hold = BEAM_HOLD;
+        } else {
+            self.beam_hold = self.beam_hold.saturating_sub(TICK_MS);
+        }
+        let beaming = self.gun.weapon().is_beam() && self.beam_hold > 0;
+        for command in commands {
+            let player = &mut self.player;
+            match command {
+                Command::Fire if beaming => (),
+                Command::Fire => {
+                    // Holding the key fires as fast as the weapon allows
+                    let shots = self.gun.fire().unwrap_or_default();
+                    if !shots.is_empty() {
+                        self.fire_drones();
+                    }
+                    for mut shot in shots {
+                        if self.focused {
+                            shot.angle *= FOCUS_SPREAD;
+                        }
+                        self.launch(self.player.pos, &shot);
+                    }
+                }
+                Command::Charge => {
+                    self.charge = (self.charge + TICK_MS).min(weapon::CHARGE_TIME + CHARGE_TAIL);
+                }
+                Command::Release => {
+                    let charge = self.held_charge();
+                    if charge >= weapon::CHARGE_MIN {
+                        for shot in weapon::charged(charge) {
+                            self.launch(self.player.pos, &shot);
+                        }
+                    }
+                    self.charge = 0;
+                }
+                Command::Bomb => {
+                    self.detonate();
+                }
+                Command::Dash => self.dodge(),
+                Command::Focus => (),
+                Command::Down => player.pos.y = (player.pos.y + stride).min((HEIGHT - 2) as f64),
+                Command::Up => player.pos.y = (player.pos.y - stride).max(1.0),
+                Command::Left => player.pos.x = (player.pos.x - stride).max(1.0),
+                Command::Right => player.pos.x = (player.pos.x + stride).min((WIDTH - 2) as f64),
+            }
+        }
+
+        self.beam = if beaming {
+            self.fire_beam()
+        } else {
+            Vec::new()
+        };
+
+        // Drones follow the player and stop enemy bullets that reach them
+        self.drones.follow(self.player.pos);
+        let drones: Vec<(i32, i32)> = self
+            .drones
+            .positions(self.player.pos, self.focused)
+            .iter()
+            .map(|d| d.cell())
+            .collect();
+        self.bullets
+            .retain(|b| b.team == Team::Player || !drones.contains(&b.pos.cell()));
+
+        // Check if player ran into enemy, an enemy bullet or a hazard
+        let cell = self.player.pos.cell();
+        for i in (0..self.enemies.len()).rev() {
+            if self.enemies[i].covers(cell) {
+                self.hurt(1);
+                self.destroy(i);
+            }
+        }
+        let damage: usize = self
+            .bullets
+            .iter()
+            .filter(|b| b.team == Team::Enemy && b.pos.cell() == cell)
+            .map(|b| b.damage)
+            .sum();
+        self.bullets
+            .retain(|b| b.team == Team::Player || b.pos.cell() != cell);
+        self.hurt(damage);
+        let hazards = self.hazards.len();
+        self.hazards.retain(|h| h.pos.cell() != cell);
+        if self.hazards.len() < hazards {
+            self.hurt(1);
+        }
+
+        // Running into the boss hurts and pushes the player back below it
+        if let Some(boss) = &self.boss {
+            if boss.covers(self.defs.boss(boss.def), cell) {
+                self.player.pos.y = (boss.body.pos.cell().1 + boss.body.size.1 as i32) as f64;
+                self.hurt(1);
+            }
+        }
+
+        self.graze();
+        self.step_pickups();
+        self.step_shockwave();
+
+        self.redraw();
+        self.tick += 1;
+    }
+
+    /// Advances the run by one tick using the inputs recorded for that tick
+    ///
+    /// # Arguments
+    /// * replay: the recording to read inputs from
+    pub fn step_recorded(&mut self, replay: &Replay) {
+        let commands: Vec<Command> = replay
+            .inputs_at(self.tick)
+            .filter_map(Command::from_char)
+            .collect();
+        self.step(&commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_game() -> Game {
+        Game::new(7, Rc::new(Registry::builtin()))
+    }
+
+    // Commands that keep a run busy moving, shooting, bombing and dashing
+    fn commands_at(tick: u64) -> Vec<Command> {
+        match tick % 40 {
+            0..=9 => vec![Command::Left, Command::Fire],
+            10..=19 => vec![Command::Right, Command::Fire, Command::Focus],
+            20 => vec![Command::Dash],
+            21..=29 => vec![Command::Charge],
+            30 => vec![Command::Release],
+            35 if tick % 400 == 35 => vec![Command::Bomb],
+            _ => vec![Command::Up],
+        }
+    }
+
+    fn play(game: &mut Game, replay: &mut Replay, ticks: u64) {
+        for _ in 0..ticks {
+            let commands = commands_at(game.tick);
+            for command in &commands {
+                replay.record(game.tick, command.to_char());
+            }
+            game.step(&commands);
+        }
+    }
+
+    fn player_bullets(game: &Game) -> usize {
+        game.bullets
+            .iter()
+            .filter(|b| b.team == Team::Player)
+            .count()
+    }
+
+    #[test]
+    fn tap_does_not_fire_charged_shot() {
+        // A tap fires once, then counts as held until `FIRE_HOLD` runs out
+        let mut game = new_game();
+        game.step(&[Command::Fire, Command::Charge]);
+        for _ in 0..CHARGE_TAIL / TICK_MS {
+            game.step(&[Command::Charge]);
+        }
+        let mut untouched = game.clone();
+        game.step(&[Command::Release]);
+        untouched.step(&[]);
+        assert_eq!(player_bullets(&game), player_bullets(&untouched));
+        assert_eq!(game.charge, 0);
+    }
+
+    #[test]
+    fn hold_fires_charged_shot() {
+        let mut game = new_game();
+        let ticks = (weapon::CHARGE_MIN + CHARGE_TAIL) / TICK_MS;
+        for _ in 0..ticks {
+            game.step(&[Command::Charge]);
+        }
+        assert!(game.held_charge() >= weapon::CHARGE_MIN);
+        let before = player_bullets(&game);
+        game.step(&[Command::Release]);
+        assert!(player_bullets(&game) > before);
+    }
+
+    #[test]
+    fn tap_does_not_hold_beam() {
+        let mut game = new_game();
+        game.gun = Gun::new(weapon::WEAPONS.len() - 1);
+        assert!(game.gun.weapon().is_beam());
+        game.step(&[Command::Fire, Command::Charge]);
+        assert!(!game.beam.is_empty());
+
+        // Fire is still held, but the key has stopped repeating
+        for _ in 0..BEAM_HOLD / TICK_MS {
+            game.step(&[Command::Charge]);
+        }
+        assert!(game.beam.is_empty());
+    }
+
+    #[test]
+    fn peeled_enemy_dives() {
+        // A scout zigzags one row per move when left to its type
+        let mut game = new_game();
+        let def = game.defs.find("scout").unwrap();
+        let size = game.defs.get(def).sprite.size();
+        let mut scout = Enemy::new(formation::slot_position(0), size, def, 1);
+        scout.slot = Some(0);
+        game.enemies.push(scout);
+        game.peel = TICK_MS;
+        game.step(&[]);
+        assert!(game.enemies[0].diving);
+
+        // Diving covers two rows a move instead
+        let top = game.enemies[0].pos.y;
+        for _ in 0..2 * PEEL_SPEED / TICK_MS {
+            game.step(&[]);
+        }
+        assert!(game.enemies[0].pos.y > top + 2.0);
+    }
+
+    #[test]
+    fn bomb_reaches_boss_parts() {
+        let mut game = new_game();
+        let mut boss = Boss::new(0, game.defs.boss(0));
+        boss.body.pos.y = 2.0;
+        let health = boss.body.health;
+        game.boss = Some(boss);
+        game.step(&[Command::Bomb]);
+        while game.shockwave.is_some() {
+            game.step(&[]);
+        }
+        assert!(game.boss.is_none_or(|b| b.body.health < health));
+    }
+
+    #[test]
+    fn lethal_hit_does_not_restart_shockwave() {
+        let mut game = new_game();
+        game.step(&[Command::Bomb]);
+        game.step(&[]);
+        let (radius, bombs) = (game.shockwave.as_ref().unwrap().radius, game.bombs);
+        game.hurt(game.player.health);
+        assert_eq!(game.shockwave.as_ref().unwrap().radius, radius);
+        assert_eq!(game.bombs, bombs);
+        assert_eq!(game.lives, LIVES - 1);
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut game = Game::for_mode(7, new_game().defs, CAMPAIGN);
+        let mut replay = Replay::new(game.seed);
+        play(&mut game, &mut replay, 300);
+        assert!(!game.is_over());
+
+        let mut read = Game::read_state(&game.write_state(), game.defs.clone()).unwrap();
+        let mut read_replay = Replay::read(&replay.write()).unwrap();
+        assert_eq!(read.write_state(), game.write_state());
+        assert_eq!(read_replay.write(), replay.write());
+
+        // Both carry on exactly the same
+        play(&mut game, &mut replay, 100);
+        play(&mut read, &mut read_replay, 100);
+        assert_eq!(read.write_state(), game.write_state());
+        assert_eq!(read_replay.write(), replay.write());
+    }
+}
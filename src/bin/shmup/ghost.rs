@@ -0,0 +1,89 @@
+use crossterm::style::Attribute;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+use text_engine::replay::Replay;
+use text_engine::storage::Storage;
+use text_engine::Layer;
+
+use crate::enemy::Registry;
+use crate::game::Game;
+use crate::GHOST_SYM;
+
+/// Returns the file holding the best run for a seed
+///
+/// # Arguments
+/// * storage: where save data is kept
+/// * seed: the seed of the run
+fn best_path(storage: &Storage, seed: u64) -> PathBuf {
+    storage.data_path(&format!("replays/best-{}.replay", seed))
+}
+
+/// Saves a finished run if it beats the best run for its seed
+///
+/// # Arguments
+/// * storage: where save data is kept
+/// * replay: the finished run
+pub fn save_if_best(storage: &Storage, replay: &Replay) -> io::Result<()> {
+    let path = best_path(storage, replay.seed);
+    if let Ok(best) = Replay::load(&path) {
+        if best.score >= replay.score {
+            return Ok(());
+        }
+    }
+    replay.save(path)
+}
+
+/// The best run for a seed, played back in lockstep with a live run
+pub struct Ghost {
+    pub game: Game,
+    replay: Replay,
+}
+impl Ghost {
+    /// Returns the ghost for a seed, if a best run has been saved for it
+    ///
+    /// # Arguments
+    /// * storage: where save data is kept
+    /// * seed: the seed of the live run
+    /// * defs: the enemy types the run can use
+    pub fn load(storage: &Storage, seed: u64, defs: Rc<Registry>) -> Option<Self> {
+        let replay = Replay::load(best_path(storage, seed)).ok()?;
+        Some(Self {
+            game: Game::new(replay.seed, defs),
+            replay,
+        })
+    }
+
+    /// Returns true once the ghost's run has ended
+    pub fn finished(&self) -> bool {
+        self.game.tick >= self.replay.length || self.game.is_over()
+    }
+
+    /// Advances the ghost by one tick, matching a step of the live run
+    pub fn step(&mut self) {
+        if !self.finished() {
+            self.game.step_recorded(&self.replay);
+        }
+    }
+
+    /// Advances the ghost to a tick, for joining a run partway through
+    ///
+    /// # Arguments
+    /// * tick: the tick the live run is on
+    pub fn catch_up(&mut self, tick: u64) {
+        while self.game.tick < tick && !self.finished() {
+            self.step();
+        }
+    }
+
+    /// Returns the layer showing the ghost's ship, empty once its run has ended
+    pub fn layer(&self) -> Layer {
+        let mut layer = Layer::new(Attribute::Dim);
+        if !self.finished() {
+            if let Some(pos) = self.game.map.inside(self.game.player.pos.cell()) {
+                layer.set(pos, GHOST_SYM);
+            }
+        }
+        layer
+    }
+}
@@ -0,0 +1,414 @@
+use crossterm::{
+    cursor,
+    terminal::{Clear, ClearType},
+    ExecutableCommand, QueueableCommand,
+};
+use std::io::{stdout, Write};
+use text_engine::leaderboard::Leaderboard;
+use text_engine::widget::Menu;
+
+use crate::dash::Dash;
+use crate::game::Game;
+use crate::pickup::Effects;
+use crate::weapon::{Gun, CHARGE_MIN, CHARGE_TIME, MAX_HEAT};
+use crate::{DASH_COOLDOWN, HEIGHT, MAX_HEALTH, WIDTH};
+
+// Columns between the two columns of the sidebar
+const COLUMN_GAP: usize = 14;
+
+// Width of the boss health bar, in characters
+const BOSS_BAR_LEN: usize = 10;
+
+// Width of the weapon heat bar, in characters
+const HEAT_BAR_LEN: usize = 10;
+
+// Width of the charged shot bar, in characters
+const CHARGE_BAR_LEN: usize = 10;
+
+// Width of the dash cooldown bar, in characters
+const DASH_BAR_LEN: usize = 10;
+
+/// Updates the health bar display
+///
+/// # Arguments
+///
+/// * health: the number of health dots to display, with any past full
+///   health shown as overshield
+pub fn update_health(health: usize) {
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            ((3 * HEIGHT) / 5 + 2) as u16,
+        ))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            (3 * HEIGHT / 5 + 2) as u16,
+        ))
+        .unwrap();
+    for i in 0..health {
+        print!("{} ", if i < MAX_HEALTH { '*' } else { '+' });
+    }
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates the number of lives left, below the health bar
+///
+/// # Arguments
+///
+/// * lives: the lives left, counting the one being played
+pub fn update_lives(lives: usize) {
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            (3 * HEIGHT / 5 + 4) as u16,
+        ))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!("LIVES {}", lives);
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates all side stats: highscore, score, and health bar
+///
+/// # Arguments
+///
+/// * highscore: current highscore
+/// * score: current score
+/// * health: the number of health dots to display
+pub fn update_stats(highscore: i32, score: i32, health: usize) {
+    stdout()
+        .queue(cursor::MoveTo((5 * WIDTH / 4) as u16, (HEIGHT / 5) as u16))
+        .unwrap();
+    print!("HIGHSCORE");
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            (HEIGHT / 5 + 2) as u16,
+        ))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!("{}", highscore);
+
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            (2 * HEIGHT / 5) as u16,
+        ))
+        .unwrap();
+    print!("SCORE");
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            (2 * HEIGHT / 5 + 2) as u16,
+        ))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!("{}", score);
+    stdout().execute(cursor::RestorePosition).unwrap();
+    stdout().flush().unwrap();
+
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            ((3 * HEIGHT) / 5) as u16,
+        ))
+        .unwrap();
+    print!("HEALTH");
+    update_health(health);
+}
+
+/// Updates the boss health bar next to the player's health, or clears it
+///
+/// Must be called after `update_stats`, which clears the rows it uses.
+///
+/// # Arguments
+///
+/// * boss: the boss's name, health and full health, if one is being fought
+pub fn update_boss(boss: Option<(&str, usize, usize)>) {
+    let x = (5 * WIDTH / 4 + COLUMN_GAP) as u16;
+    for row in [3 * HEIGHT / 5, 3 * HEIGHT / 5 + 2] {
+        stdout().queue(cursor::MoveTo(x, row as u16)).unwrap();
+        stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    }
+    if let Some((name, health, max)) = boss {
+        let filled = (health * BOSS_BAR_LEN).div_ceil(max.max(1));
+        stdout()
+            .queue(cursor::MoveTo(x, (3 * HEIGHT / 5) as u16))
+            .unwrap();
+        print!("{}", name.to_uppercase());
+        stdout()
+            .queue(cursor::MoveTo(x, (3 * HEIGHT / 5 + 2) as u16))
+            .unwrap();
+        print!(
+            "[{}{}]",
+            "#".repeat(filled),
+            "-".repeat(BOSS_BAR_LEN - filled)
+        );
+    }
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates the player's weapon, its level and its heat next to the score
+///
+/// Must be called after `update_stats`, which clears the rows it uses. The
+/// heat bar fills with `!` while the weapon is overheated.
+///
+/// # Arguments
+///
+/// * gun: the player's weapon
+pub fn update_weapon(gun: &Gun) {
+    let x = (5 * WIDTH / 4 + COLUMN_GAP) as u16;
+    stdout()
+        .queue(cursor::MoveTo(x, (2 * HEIGHT / 5) as u16))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!("{} {}", gun.weapon().name().to_uppercase(), gun.level);
+
+    let filled = (gun.heat as usize * HEAT_BAR_LEN).div_ceil(MAX_HEAT as usize);
+    stdout()
+        .queue(cursor::MoveTo(x, (2 * HEIGHT / 5 + 2) as u16))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!(
+        "[{}{}]",
+        (if gun.overheated { "!" } else { "#" }).repeat(filled),
+        "-".repeat(HEAT_BAR_LEN - filled)
+    );
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates the charge of the player's next charged shot, under their weapon
+///
+/// The bar fills with `=` until letting go would fire a charged shot, then
+/// with `>`.
+///
+/// # Arguments
+///
+/// * charge: milliseconds of charge letting go would use
+pub fn update_charge(charge: u128) {
+    let x = (5 * WIDTH / 4 + COLUMN_GAP) as u16;
+    let filled = (charge.min(CHARGE_TIME) * CHARGE_BAR_LEN as u128 / CHARGE_TIME) as usize;
+    stdout()
+        .queue(cursor::MoveTo(x, (2 * HEIGHT / 5 + 4) as u16))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!(
+        "[{}{}]",
+        (if charge >= CHARGE_MIN { ">" } else { "=" }).repeat(filled),
+        "-".repeat(CHARGE_BAR_LEN - filled)
+    );
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates the timed effects of pickups next to the highscore
+///
+/// Must be called after `update_stats`, which clears the rows it uses.
+///
+/// # Arguments
+///
+/// * effects: the effects on the player
+pub fn update_effects(effects: &Effects) {
+    let x = (5 * WIDTH / 4 + COLUMN_GAP) as u16;
+    let active = effects.active();
+    for i in 0..3 {
+        stdout()
+            .queue(cursor::MoveTo(x, (HEIGHT / 5 + i) as u16))
+            .unwrap();
+        stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+        if let Some((name, time)) = active.get(i) {
+            print!("{} {}s", name, time.div_ceil(1000));
+        }
+    }
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates the score multiplier and combo, in a third column of the sidebar
+///
+/// # Arguments
+///
+/// * multiplier: what points scored are multiplied by
+/// * combo: kills in the current combo
+pub fn update_combo(multiplier: i32, combo: usize) {
+    let x = (5 * WIDTH / 4 + 2 * COLUMN_GAP) as u16;
+    let readouts = [
+        (2 * HEIGHT / 5, "MULTIPLIER", format!("x{}", multiplier)),
+        (3 * HEIGHT / 5, "COMBO", combo.to_string()),
+    ];
+    for (row, heading, value) in readouts.iter() {
+        stdout().queue(cursor::MoveTo(x, *row as u16)).unwrap();
+        print!("{}", heading);
+        stdout().queue(cursor::MoveTo(x, (row + 2) as u16)).unwrap();
+        stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+        print!("{}", value);
+    }
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates how far the dash has cooled down, above the score multiplier
+///
+/// Must be called after `update_effects`, which clears the rows it uses. The
+/// bar is full once the player can dash again.
+///
+/// # Arguments
+///
+/// * dash: the player's dash
+pub fn update_dash(dash: &Dash) {
+    let x = (5 * WIDTH / 4 + 2 * COLUMN_GAP) as u16;
+    stdout()
+        .queue(cursor::MoveTo(x, (HEIGHT / 5) as u16))
+        .unwrap();
+    print!("DASH");
+    let cooled = (DASH_COOLDOWN - dash.cooldown.min(DASH_COOLDOWN)) as usize;
+    let filled = cooled * DASH_BAR_LEN / DASH_COOLDOWN as usize;
+    stdout()
+        .queue(cursor::MoveTo(x, (HEIGHT / 5 + 2) as u16))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!(
+        "[{}{}]",
+        "#".repeat(filled),
+        "-".repeat(DASH_BAR_LEN - filled)
+    );
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates the number of bombs left next to the ghost score
+///
+/// Must be called after `update_ghost`, which clears the rows it uses.
+///
+/// # Arguments
+///
+/// * bombs: the bombs the player has left
+pub fn update_bombs(bombs: usize) {
+    let x = (5 * WIDTH / 4 + COLUMN_GAP) as u16;
+    stdout()
+        .queue(cursor::MoveTo(x, (4 * HEIGHT / 5) as u16))
+        .unwrap();
+    print!("BOMBS");
+    stdout()
+        .queue(cursor::MoveTo(x, (4 * HEIGHT / 5 + 2) as u16))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    for _i in 0..bombs {
+        print!("B ");
+    }
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Updates the score difference to the ghost run
+///
+/// # Arguments
+///
+/// * delta: current score minus the ghost's score at the same moment
+pub fn update_ghost(delta: i32) {
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            ((4 * HEIGHT) / 5) as u16,
+        ))
+        .unwrap();
+    print!("GHOST");
+    stdout()
+        .queue(cursor::MoveTo(
+            (5 * WIDTH / 4) as u16,
+            ((4 * HEIGHT) / 5 + 2) as u16,
+        ))
+        .unwrap();
+    stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+    print!("{:+}", delta);
+    stdout().execute(cursor::RestorePosition).unwrap();
+}
+
+/// Redraws the whole sidebar for a game
+///
+/// Must be called after `update_ghost`, which clears the rows the bomb count
+/// uses.
+///
+/// # Arguments
+///
+/// * game: the game to show the stats of
+/// * highscore: current highscore
+pub fn redraw(game: &Game, highscore: i32) {
+    update_stats(highscore, game.score, game.player.health);
+    update_lives(game.lives);
+    update_boss(game.boss_bar());
+    update_weapon(&game.gun);
+    update_charge(game.held_charge());
+    update_effects(&game.effects);
+    update_combo(game.multiplier(), game.combo.count);
+    update_dash(&game.dash);
+    update_bombs(game.bombs);
+}
+
+/// Prints a block of text one line at a time, starting at the left edge
+///
+/// # Arguments
+///
+/// * row: the terminal row of the first line
+/// * text: the text to print
+pub fn print_text(row: usize, text: &str) {
+    for (i, line) in text.lines().enumerate() {
+        stdout().queue(cursor::MoveTo(0, (row + i) as u16)).unwrap();
+        print!("{}", line);
+    }
+    stdout().flush().unwrap();
+}
+
+/// Displays the highscores screen for one game mode
+///
+/// # Arguments
+///
+/// * leaderboard: the saved runs
+/// * mode: the game mode to list
+pub fn display_scores(leaderboard: &Leaderboard, mode: &str) {
+    stdout().queue(Clear(ClearType::All)).unwrap();
+    stdout().queue(cursor::MoveTo(2, 1)).unwrap();
+    print!("HIGHSCORES - {}", mode.to_uppercase());
+    stdout().queue(cursor::MoveTo(2, 3)).unwrap();
+    print!(" #  NAME         SCORE   TIME  DATE        SEED");
+    let mut rows = 0;
+    for (i, e) in leaderboard.top(mode).enumerate() {
+        stdout().queue(cursor::MoveTo(2, (5 + i) as u16)).unwrap();
+        print!(
+            "{:>2}  {:<10}  {:>6}  {:>2}:{:02}  {:<10}  {}",
+            i + 1,
+            e.name,
+            e.score,
+            e.duration / 60,
+            e.duration % 60,
+            e.date,
+            e.seed
+        );
+        rows += 1;
+    }
+    if rows == 0 {
+        stdout().queue(cursor::MoveTo(2, 5)).unwrap();
+        print!("No scores yet");
+    }
+    stdout()
+        .queue(cursor::MoveTo(2, (HEIGHT - 2) as u16))
+        .unwrap();
+    print!("LEFT/RIGHT change mode   ESC back");
+    stdout().flush().unwrap();
+}
+
+/// Displays the save slot picker
+///
+/// # Arguments
+///
+/// * heading: what picking a slot will do
+/// * menu: the slots to choose from
+pub fn display_slots(heading: &str, menu: &Menu) {
+    stdout().queue(Clear(ClearType::All)).unwrap();
+    stdout().queue(cursor::MoveTo(2, 1)).unwrap();
+    print!("{}", heading);
+    menu.display((2, 3));
+    stdout()
+        .queue(cursor::MoveTo(2, (HEIGHT - 2) as u16))
+        .unwrap();
+    print!("ENTER select   ESC back");
+    stdout().flush().unwrap();
+}
@@ -0,0 +1,351 @@
+use text_engine::data::DataError;
+
+use crate::enemy::Registry;
+use crate::formation;
+use crate::{HEIGHT, WIDTH};
+
+/// How a group of enemies spawned together is laid out
+#[derive(Clone, Copy, PartialEq)]
+pub enum Formation {
+    Row,    // Side by side along the top
+    Column, // One above the other, flying in one after another
+    Vee,    // Side by side, with the middle one leading
+}
+
+/// One line of a level script
+pub enum Event {
+    Spawn {
+        def: usize,       // Index of the enemy type
+        x: Option<usize>, // Left column of the group, or None for a random one
+        formation: Formation,
+        count: usize,
+    },
+    Wait(u128),      // Milliseconds before the next event
+    Clear,           // Wait until every enemy and boss is destroyed
+    Message(String), // Text shown across the map for a while
+    Boss(usize),     // Index of a boss to bring in
+    Enter {
+        def: usize,     // Index of the enemy type
+        path: usize,    // Index of the path it flies in along
+        mirrored: bool, // Fly the path flipped left to right
+    },
+    Peel(u128), // Milliseconds between enemies peeling off the formation, 0 for never
+    Mark,       // Where the next loop goes back to
+    Loop {
+        start: usize,       // Event to go back to
+        count: Option<u64>, // Times to go back, or None for forever
+    },
+}
+
+/// An authored level: a script of events run one after another
+pub struct Level {
+    pub name: String,
+    pub events: Vec<Event>,
+}
+impl Level {
+    /// Reads a level from its script
+    ///
+    /// Each line is one event:
+    /// ```text
+    /// # Comments start with #
+    /// message STAGE 1
+    /// spawn grunt 5
+    /// spawn scout random row 3
+    /// wait 1500
+    /// clear
+    /// boss warden
+    /// mark
+    /// enter grunt swoop mirror
+    /// peel 2000
+    /// wait 300
+    /// loop 2
+    /// ```
+    ///
+    /// `spawn TYPE X [row|column|vee COUNT]` brings in enemies with their
+    /// left edge at column X, or at a random column. `wait MS` pauses the
+    /// script, `clear` waits until every enemy and boss is destroyed, and
+    /// `loop [COUNT]` goes back to the last `mark` or `loop`, or the start of
+    /// the script, COUNT more times or forever. Something it goes back over
+    /// has to wait more than 0 milliseconds or clear.
+    ///
+    /// `enter TYPE PATH [mirror]` flies an enemy in along a path to the first
+    /// open slot in the formation, and `peel MS` has one enemy waiting in the
+    /// formation peel off and dive at the player every MS milliseconds.
+    ///
+    /// # Arguments
+    /// * name: the level's name
+    /// * file: name of the file, used in errors
+    /// * text: the script
+    /// * defs: the enemy types and bosses the level can use
+    pub fn parse(name: &str, file: &str, text: &str, defs: &Registry) -> Result<Self, DataError> {
+        let mut events = Vec::new();
+        // Event a loop goes back to, and whether anything since then waits
+        let mut start = 0;
+        let mut waits = false;
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| DataError::new(file, i + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let number = |index: usize| -> Result<u64, DataError> {
+                parts[index]
+                    .parse()
+                    .map_err(|_| error(&format!("invalid number {}", parts[index])))
+            };
+
+            let event = match (parts[0], parts.len()) {
+                ("spawn", 3) | ("spawn", 5) => {
+                    let def = defs
+                        .find(parts[1])
+                        .ok_or_else(|| error(&format!("unknown enemy type {}", parts[1])))?;
+                    let (formation, count) = match parts.get(3).copied() {
+                        None => (Formation::Row, 1),
+                        Some("row") => (Formation::Row, number(4)?),
+                        Some("column") => (Formation::Column, number(4)?),
+                        Some("vee") => (Formation::Vee, number(4)?),
+                        Some(other) => return Err(error(&format!("unknown formation {}", other))),
+                    };
+                    let count = count as usize;
+
+                    // The whole group has to fit between the walls
+                    if count == 0 || count > WIDTH {
+                        return Err(error("the group does not fit on the map"));
+                    }
+                    let (width, height) = defs.get(def).sprite.size();
+                    let span = match formation {
+                        Formation::Column => width,
+                        _ => count * (width + 1) - 1,
+                    };
+                    // Enemies that do not fit at the top wait above the map, but
+                    // no more than a map's height above it
+                    let depth = match formation {
+                        Formation::Row => height,
+                        Formation::Column => count * (height + 1) - 1,
+                        Formation::Vee => count / 2 + height,
+                    };
+                    if depth > HEIGHT {
+                        return Err(error("the group does not fit above the map"));
+                    }
+                    let x = match parts[2] {
+                        "random" => None,
+                        _ => Some(number(2)? as usize),
+                    };
+                    if span > WIDTH - 2 || x.is_some_and(|x| x < 1 || x + span > WIDTH - 1) {
+                        return Err(error("the group does not fit on the map"));
+                    }
+                    Event::Spawn {
+                        def,
+                        x,
+                        formation,
+                        count,
+                    }
+                }
+                ("wait", 2) => {
+                    let time = number(1)? as u128;
+                    waits |= time > 0;
+                    Event::Wait(time)
+                }
+                ("clear", 1) => {
+                    waits = true;
+                    Event::Clear
+                }
+                ("message", n) if n > 1 => Event::Message(parts[1..].join(" ")),
+                ("boss", 2) => Event::Boss(
+                    defs.find_boss(parts[1])
+                        .ok_or_else(|| error(&format!("unknown boss {}", parts[1])))?,
+                ),
+                ("enter", 3) | ("enter", 4) => {
+                    let def = defs
+                        .find(parts[1])
+                        .ok_or_else(|| error(&format!("unknown enemy type {}", parts[1])))?;
+                    if !formation::fits(defs.get(def).sprite.size()) {
+                        return Err(error(&format!(
+                            "{} is too big to join the formation",
+                            parts[1]
+                        )));
+                    }
+                    let path = defs
+                        .find_path(parts[2])
+                        .ok_or_else(|| error(&format!("unknown path {}", parts[2])))?;
+                    let mirrored = match parts.get(3).copied() {
+                        None => false,
+                        Some("mirror") => true,
+                        Some(other) => return Err(error(&format!("unknown setting {}", other))),
+                    };
+                    Event::Enter {
+                        def,
+                        path,
+                        mirrored,
+                    }
+                }
+                ("peel", 2) => Event::Peel(number(1)? as u128),
+                ("mark", 1) => {
+                    start = events.len() + 1;
+                    waits = false;
+                    Event::Mark
+                }
+                ("loop", 1) | ("loop", 2) => {
+                    // A loop that never waits would never let the run go on
+                    if !waits {
+                        return Err(error(
+                            "loop needs a wait of more than 0 or a clear before it",
+                        ));
+                    }
+                    let count = match parts.len() {
+                        2 => Some(number(1)?),
+                        _ => None,
+                    };
+                    let event = Event::Loop { start, count };
+                    start = events.len() + 1;
+                    waits = false;
+                    event
+                }
+                ("spawn", _)
+                | ("wait", _)
+                | ("message", _)
+                | ("clear", _)
+                | ("boss", _)
+                | ("enter", _)
+                | ("peel", _)
+                | ("mark", _)
+                | ("loop", _) => {
+                    return Err(error(&format!("wrong number of settings for {}", parts[0])))
+                }
+                (other, _) => return Err(error(&format!("unknown event {}", other))),
+            };
+            events.push(event);
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            events,
+        })
+    }
+}
+
+/// Where a run is in the levels, for runs played through them
+#[derive(Clone)]
+pub struct Script {
+    pub level: usize, // Index of the level being played
+    pub next: usize,  // Index of the next event to run
+    pub wait: u128,   // Milliseconds left before the next event
+    pub repeats: u64, // Times the current loop has gone back
+}
+impl Script {
+    /// Returns a script at the start of the first level
+    pub fn new() -> Self {
+        Self {
+            level: 0,
+            next: 0,
+            wait: 0,
+            repeats: 0,
+        }
+    }
+}
+impl Default for Script {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Level, DataError> {
+        let defs = Registry::builtin();
+        Level::parse("test", "test.txt", text, &defs)
+    }
+
+    #[test]
+    fn reads_script() {
+        let level = parse(
+            "# comment\n\
+             message STAGE 1\n\
+             spawn grunt 5\n\
+             spawn scout random vee 3\n\
+             wait 1500\n\
+             mark\n\
+             enter grunt swoop mirror\n\
+             peel 2000\n\
+             clear\n\
+             loop 2\n\
+             boss warden\n",
+        )
+        .unwrap();
+        assert_eq!(level.events.len(), 10);
+        assert!(matches!(&level.events[0], Event::Message(m) if m == "STAGE 1"));
+        assert!(matches!(
+            level.events[2],
+            Event::Spawn {
+                x: None,
+                formation: Formation::Vee,
+                count: 3,
+                ..
+            }
+        ));
+        assert!(matches!(level.events[3], Event::Wait(1500)));
+        assert!(matches!(
+            level.events[5],
+            Event::Enter { mirrored: true, .. }
+        ));
+        // The loop goes back to just after the mark
+        assert!(matches!(
+            level.events[8],
+            Event::Loop {
+                start: 5,
+                count: Some(2)
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_scripts() {
+        let error = |text: &str| parse(text).err().unwrap().to_string();
+        assert_eq!(
+            error("wait 100\n\nspawn nobody 5\n"),
+            "test.txt:3: unknown enemy type nobody"
+        );
+        assert_eq!(
+            error("spawn grunt 28\n"),
+            "test.txt:1: the group does not fit on the map"
+        );
+        assert_eq!(error("wait soon\n"), "test.txt:1: invalid number soon");
+        assert_eq!(
+            error("clear now\n"),
+            "test.txt:1: wrong number of settings for clear"
+        );
+        assert_eq!(
+            error("message\n"),
+            "test.txt:1: wrong number of settings for message"
+        );
+        assert_eq!(error("dance\n"), "test.txt:1: unknown event dance");
+    }
+
+    #[test]
+    fn rejects_loops_that_never_wait() {
+        let error = |text: &str| parse(text).err().unwrap().to_string();
+        let message = "loop needs a wait of more than 0 or a clear before it";
+        assert_eq!(
+            error("spawn grunt 5\nloop\n"),
+            format!("test.txt:2: {}", message)
+        );
+        assert_eq!(
+            error("wait 0\nloop 3\n"),
+            format!("test.txt:2: {}", message)
+        );
+        // Waiting before the mark does not count
+        assert_eq!(
+            error("wait 100\nmark\nspawn grunt 5\nloop\n"),
+            format!("test.txt:4: {}", message)
+        );
+        // Nor does waiting before the last loop
+        assert_eq!(
+            error("clear\nloop 1\nspawn grunt 5\nloop\n"),
+            format!("test.txt:4: {}", message)
+        );
+        assert!(parse("mark\nwait 1\nloop\n").is_ok());
+    }
+}
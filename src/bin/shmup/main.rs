@@ -0,0 +1,727 @@
+use crossterm::{
+    cursor,
+    event::{poll, read, Event, KeyCode, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    ExecutableCommand, QueueableCommand,
+};
+use crossterm::{
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+};
+use rand::{thread_rng, Rng};
+use std::env;
+use std::io::{stdout, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use text_engine::input::{self, Held};
+use text_engine::leaderboard::{self, Entry, Leaderboard};
+use text_engine::replay::Replay;
+use text_engine::storage;
+use text_engine::widget::{Menu, TextInput};
+use text_engine::*;
+
+mod bomb;
+mod boss;
+mod dash;
+mod death;
+mod drone;
+mod enemy;
+mod formation;
+mod game;
+mod ghost;
+mod hud;
+mod level;
+mod movement;
+mod pickup;
+mod save;
+mod score;
+mod viewer;
+mod weapon;
+
+use enemy::Registry;
+use game::{Command, Game, TICK_MS};
+use ghost::Ghost;
+use hud::{display_scores, display_slots, print_text, redraw, update_ghost};
+use save::SavedRun;
+use storage::Storage;
+use viewer::ReplayViewer;
+
+// Map dimensions
+const HEIGHT: usize = 30;
+const WIDTH: usize = 30;
+
+const PLAYER_SYM: char = '@';
+const BULLET_SYM: char = '|';
+const GHOST_SYM: char = '@';
+const MINE_SYM: char = '+';
+const HAZARD_SYM: char = '~';
+const SHOCKWAVE_SYM: char = '*';
+const DRONE_SYM: char = '%';
+const BEAM_SYM: char = '!';
+
+const ENEMY_GEN_SPD: u128 = 1000; // Enemy generation speed
+const BOSS_GEN_SPD: u128 = 45000; // Milliseconds of regular play between bosses
+const MESSAGE_TIME: u128 = 2500; // Milliseconds a level's messages are shown
+const BULLET_SPD: u128 = 200; // Bullet movement speed
+const PEEL_SPEED: u128 = 150; // Milliseconds per move of an enemy diving out of a formation
+
+const MAX_HEALTH: usize = 3;
+const FOCUS_HOLD: u64 = 500; // Milliseconds focus stays on after the last key pressed with Shift
+const FOCUS_STRIDE: f64 = 0.5; // Fraction of a move the player makes while focused
+const FOCUS_SPREAD: f64 = 0.5; // Fraction of a weapon's spread it keeps while focused
+const FIRE_HOLD: u64 = 500; // Milliseconds fire stays held after the key last repeated
+const BEAM_HOLD: u128 = 100; // Milliseconds a beam stays on after the fire key last repeated
+const LIVES: usize = 3; // Lives the player starts with
+const MAX_LIVES: usize = 5; // Most lives the player can have
+const LIFE_SCORE: i32 = 20000; // A life is earned each time the score passes a multiple of this
+const INVINCIBLE_TIME: u128 = 1500; // Milliseconds the player can't be hurt for after a hit
+const BLINK: u128 = 100; // Milliseconds the player is shown or hidden for while invincible
+const RESPAWN_SHIELD: u128 = 2000; // Milliseconds of shield the player respawns with
+const DASH_DISTANCE: usize = 4; // Cells a dash moves the player, unless a wall is in the way
+const DASH_COOLDOWN: u128 = 1500; // Milliseconds after a dash before the player can dash again
+const DASH_INVINCIBLE: u128 = 300; // Milliseconds the player can't be hurt for after a dash
+const TRAIL_TIME: u128 = 250; // Milliseconds the after-images of a dash are shown for
+const MAX_DRONES: usize = 4; // Most option drones the player can have
+const DRONE_SPACING: usize = 2; // Moves of the player between one drone and the next
+
+const HIT_FLASH: u128 = 100; // Milliseconds an enemy flashes after it is hit
+const FAST_KILL: u128 = 3000; // Destroying an enemy this soon after it appears earns a bonus
+const FAST_KILL_BONUS: i32 = 50; // Percent of an enemy's score added for a fast kill
+const OVERKILL_BONUS: i32 = 10; // Points for each point of damage beyond what destroys an enemy
+const COMBO_WINDOW: u128 = 2000; // Milliseconds after a kill before the combo starts to decay
+const COMBO_DECAY: u128 = 500; // Milliseconds between kills lost from a decaying combo
+const COMBO_STEP: usize = 5; // Kills in a combo for each step up in its multiplier
+const MAX_COMBO_MULTIPLIER: usize = 8; // Most a combo can multiply points by
+const GRAZE_POINTS: i32 = 10; // Points for an enemy bullet passing right next to the player
+const POPUP_TIME: u128 = 800; // Milliseconds points scored float on the map for
+const POPUP_SPEED: f64 = 4.0; // Rows per second points scored float up at
+
+const PICKUP_SPEED: f64 = 3.0; // Cells per second pickups drift down at
+const MAGNET_RADIUS: f64 = 5.0; // Pickups this many cells from the player fly to them
+const MAGNET_SPEED: f64 = 12.0; // Cells per second pickups fly to the player at
+const OVERSHIELD: usize = 2; // Health a heal can add beyond MAX_HEALTH
+const SHIELD_TIME: u128 = 5000; // Milliseconds a shield absorbs hits for
+const MULTIPLIER_TIME: u128 = 8000; // Milliseconds a score multiplier lasts
+const MULTIPLIER: i32 = 2; // Points are multiplied by this while a multiplier lasts
+const SPEED_TIME: u128 = 8000; // Milliseconds a speed boost lasts
+
+const BOMBS: usize = 2; // Bombs the player starts with
+const MAX_BOMBS: usize = 5; // Most bombs the player can carry
+const BOMB_SCORE: i32 = 5000; // A bomb is earned each time the score passes a multiple of this
+const BOMB_DAMAGE: usize = 3; // Health a bomb's shockwave takes from each enemy it reaches
+const SHOCKWAVE_SPEED: f64 = 40.0; // Rows per second a bomb's shockwave spreads at
+
+// Saved best runs, kept per game mode
+const LEADERBOARD_FILE: &str = "leaderboard.txt";
+const LEADERBOARD_SIZE: usize = 10;
+const NAME_LEN: usize = 10;
+
+// Single highscore kept by older versions, in the working directory
+const LEGACY_HIGHSCORE_FILE: &str = "highscore.txt";
+
+// Recording of the most recent run
+const REPLAY_FILE: &str = "replays/last.replay";
+
+// Screen text
+const TITLE_TEXT: &str = include_str!("../../../title.txt");
+const PAUSE_TEXT: &str = include_str!("../../../pause.txt");
+
+// Game modes: random enemies every run, the same seed every run, or the
+// authored levels in order
+const MODES: [&str; 3] = ["endless", "seeded", CAMPAIGN];
+const CAMPAIGN: &str = "campaign";
+
+/// Command line options
+struct Options {
+    seed: Option<u64>,           // Seed for every run, random if not given
+    ghost: bool,                 // Show the best run for the seed while playing
+    data_dir: Option<PathBuf>,   // Where to keep save data
+    config_dir: Option<PathBuf>, // Where to look for configuration
+}
+
+/// Reads the command line options, exiting with usage on bad input
+fn parse_args() -> Options {
+    let mut options = Options {
+        seed: None,
+        ghost: false,
+        data_dir: None,
+        config_dir: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(seed) => options.seed = Some(seed),
+                None => usage(),
+            },
+            "--ghost" => options.ghost = true,
+            "--data-dir" => match args.next() {
+                Some(dir) => options.data_dir = Some(PathBuf::from(dir)),
+                None => usage(),
+            },
+            "--config-dir" => match args.next() {
+                Some(dir) => options.config_dir = Some(PathBuf::from(dir)),
+                None => usage(),
+            },
+            _ => usage(),
+        }
+    }
+    // A ghost needs the same seed every run
+    if options.ghost && options.seed.is_none() {
+        options.seed = Some(0);
+    }
+    options
+}
+
+/// Prints command line usage and exits
+fn usage() -> ! {
+    eprintln!("usage: shmup [--seed N] [--ghost] [--data-dir DIR] [--config-dir DIR]");
+    process::exit(2);
+}
+
+/// Reads saved highscores, importing an older `highscore.txt` on first run
+///
+/// # Arguments
+///
+/// * storage: where save data is kept
+fn load_leaderboard(storage: &Storage) -> Leaderboard {
+    let path = storage.data_path(LEADERBOARD_FILE);
+    match Leaderboard::load(&path, LEADERBOARD_SIZE) {
+        Ok(leaderboard) => leaderboard,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let legacy = [
+                storage.data_path(LEGACY_HIGHSCORE_FILE),
+                PathBuf::from(LEGACY_HIGHSCORE_FILE),
+            ];
+            for file in &legacy {
+                if let Ok(leaderboard) = Leaderboard::load_legacy(file, MODES[0], LEADERBOARD_SIZE)
+                {
+                    // The imported scores are still shown if they can't be written back
+                    if let Err(e) = leaderboard.save(&path) {
+                        eprintln!("could not write {}: {}", path.display(), e);
+                    }
+                    return leaderboard;
+                }
+            }
+            Leaderboard::new(LEADERBOARD_SIZE)
+        }
+        Err(e) => {
+            eprintln!("could not read {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Returns the title screen menu, offering to continue if a run is saved
+///
+/// # Arguments
+///
+/// * storage: where save data is kept
+/// * defs: the enemy types saved runs can use
+fn build_title_menu(storage: &Storage, defs: &Rc<Registry>) -> Menu {
+    if save::summaries(storage, defs).iter().any(|s| s.is_some()) {
+        Menu::new(&[
+            "Continue",
+            "Play",
+            "Campaign",
+            "Watch last run",
+            "Highscores",
+            "Quit",
+        ])
+    } else {
+        Menu::new(&["Play", "Campaign", "Watch last run", "Highscores", "Quit"])
+    }
+}
+
+/// Returns the save slot menu
+///
+/// # Arguments
+///
+/// * storage: where save data is kept
+/// * defs: the enemy types saved runs can use
+fn build_slot_menu(storage: &Storage, defs: &Rc<Registry>) -> Menu {
+    let items: Vec<String> = save::summaries(storage, defs)
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("Slot {}  {}", i + 1, s.as_deref().unwrap_or("empty")))
+        .collect();
+    let items: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
+    Menu::new(&items)
+}
+
+fn main() {
+    let options = parse_args();
+    let storage = Storage::resolve(
+        "shmup",
+        options.data_dir.clone(),
+        options.config_dir.clone(),
+    );
+    let replay_file = storage.data_path(REPLAY_FILE);
+    let leaderboard_file = storage.data_path(LEADERBOARD_FILE);
+    let mut leaderboard = load_leaderboard(&storage);
+    let defs = match Registry::load(&storage) {
+        Ok(defs) => Rc::new(defs),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    let new_seed = || options.seed.unwrap_or_else(|| thread_rng().gen());
+    let default_mode = if options.seed.is_some() {
+        MODES[1]
+    } else {
+        MODES[0]
+    };
+    let mut game_mode = default_mode;
+
+    execute!(stdout(), EnterAlternateScreen).unwrap();
+    enable_raw_mode().unwrap();
+    execute!(stdout(), Clear(ClearType::All)).unwrap();
+    stdout().execute(cursor::MoveTo(0, 0)).unwrap();
+
+    let mut mode = GameMode::Title;
+
+    // Current run and its recording
+    let mut game = Game::new(new_seed(), defs.clone());
+    let mut replay = Replay::new(game.seed);
+    replay.mode = game_mode.to_string();
+    let mut ghost = if options.ghost {
+        Ghost::load(&storage, game.seed, defs.clone())
+    } else {
+        None
+    };
+    let mut viewer: Option<ReplayViewer> = None;
+
+    // Empty map and menu shown on the title screen
+    let mut title_map = Map::new(WIDTH, HEIGHT);
+    title_map.generate();
+    let mut title_menu = build_title_menu(&storage, &defs);
+
+    // Pause menu, and save slots for saving from it or continuing from the title
+    let mut pause_menu = Menu::new(&["Resume", "Save & Quit", "Quit"]);
+    let mut slots = build_slot_menu(&storage, &defs);
+    let mut saving = false;
+
+    // Why saving last failed, shown on the screen it failed from
+    let mut save_error: Option<String> = None;
+
+    let mut highscore = leaderboard.best(game_mode);
+    let mut held = Held::new(FOCUS_HOLD);
+    let mut keys = Held::new(FIRE_HOLD);
+    let mut name_entry: Option<TextInput> = None;
+    let mut scores_mode = 0;
+
+    loop {
+        // Hide cursor and save the beginning position of the cursor
+        stdout().execute(cursor::Hide).unwrap();
+        stdout().execute(cursor::MoveTo(0, 0)).unwrap();
+        stdout().execute(cursor::SavePosition).unwrap();
+
+        match mode {
+            GameMode::Playing => {
+                // Collect key presses for one tick
+                // WASD to move player
+                // Up to shoot, held to charge a shot fired on letting go
+                // Down to bomb
+                // Space to dash
+                // Shift held with any of them to focus
+                // Esc to go to quit menu
+                let mut commands = Vec::new();
+                let frame = Instant::now();
+                let tick = Duration::from_millis(TICK_MS as u64);
+                while let Some(left) = tick.checked_sub(frame.elapsed()) {
+                    if !poll(left).unwrap() {
+                        break;
+                    }
+                    if let Event::Key(event) = read().unwrap() {
+                        if let KeyCode::Esc = event.code {
+                            mode = GameMode::Pause;
+                            break;
+                        }
+                        let (code, shifted) = input::unshift(event);
+                        if shifted {
+                            held.press(KeyModifiers::SHIFT);
+                        }
+                        keys.press(code);
+                        if let Some(command) = Command::from_key(code) {
+                            commands.push(command);
+                        }
+                    }
+                }
+                if held.is_held(KeyModifiers::SHIFT) {
+                    commands.push(Command::Focus);
+                }
+                commands.extend(Command::from_held(&mut keys));
+
+                for command in &commands {
+                    replay.record(game.tick, command.to_char());
+                }
+                game.step(&commands);
+                if let Some(g) = ghost.as_mut() {
+                    g.step();
+                }
+                if game.score > highscore {
+                    highscore = game.score;
+                }
+
+                // Refresh screen/map to update changes
+                stdout().queue(cursor::RestorePosition).unwrap();
+                let mut under: Vec<Layer> = ghost.iter().map(|g| g.layer()).collect();
+                under.push(game.trail_layer());
+                game.map.display_all(
+                    &under,
+                    &[
+                        game.flash_layer(),
+                        game.shockwave_layer(),
+                        game.hitbox_layer(),
+                    ],
+                );
+
+                // Display side stats (highscore, score, and health bar)
+                if let Some(g) = &ghost {
+                    update_ghost(game.score - g.game.score);
+                }
+                redraw(&game, highscore);
+
+                if game.is_over() {
+                    replay.finish(game.tick, game.score);
+                    let mut saved = replay.save(&replay_file);
+                    // Ghosts are only saved for seeded runs outside the campaign, as
+                    // only those can be replayed against
+                    if options.seed.is_some() && game_mode != CAMPAIGN {
+                        saved = saved.and(ghost::save_if_best(&storage, &replay));
+                    }
+                    if let Err(e) = saved {
+                        save_error = Some(format!("Could not save replay:\n{}", e));
+                    }
+                    if leaderboard.qualifies(game_mode, game.score) {
+                        name_entry = Some(TextInput::new(NAME_LEN));
+                    }
+                    mode = GameMode::GameOver;
+                }
+            }
+            GameMode::Pause => {
+                // Display pause menu
+                print_text(HEIGHT / 3, PAUSE_TEXT);
+                pause_menu.display((8, (HEIGHT / 3 + 5) as u16));
+                if let Some(error) = &save_error {
+                    print_text(HEIGHT / 3 + 9, error);
+                }
+                stdout().execute(cursor::RestorePosition).unwrap();
+
+                // Up/Down and Enter to pick an option
+                // Esc to return to game
+                let key_pressed;
+                if let Event::Key(event) = read().unwrap() {
+                    key_pressed = event;
+                    if let KeyCode::Esc = key_pressed.code {
+                        save_error = None;
+                        mode = GameMode::Playing;
+                    }
+                    if pause_menu.handle_key(key_pressed.code) {
+                        save_error = None;
+                        match pause_menu.choice() {
+                            "Resume" => mode = GameMode::Playing,
+                            "Save & Quit" => {
+                                slots = build_slot_menu(&storage, &defs);
+                                saving = true;
+                                mode = GameMode::Slots;
+                            }
+                            _ => {
+                                disable_raw_mode().unwrap();
+                                execute!(stdout(), LeaveAlternateScreen).unwrap();
+                                break;
+                            }
+                        }
+                        pause_menu.selected = 0;
+                    }
+                }
+            }
+            GameMode::Title => {
+                // Display rules and map, wait for input
+                title_map.display();
+
+                // Title text and rules
+                print_text(HEIGHT / 3, TITLE_TEXT);
+                // One row under the text, so six options still fit above the ground
+                title_menu.display((8, (HEIGHT / 3 + TITLE_TEXT.lines().count() + 1) as u16));
+                stdout().execute(cursor::MoveTo(0, 0)).unwrap();
+
+                // Up/Down and Enter to pick an option
+                // Esc to quit
+                let key_pressed;
+                if let Event::Key(event) = read().unwrap() {
+                    key_pressed = event;
+                    if let KeyCode::Esc = key_pressed.code {
+                        disable_raw_mode().unwrap();
+                        execute!(stdout(), LeaveAlternateScreen).unwrap();
+                        break;
+                    }
+                    if title_menu.handle_key(key_pressed.code) {
+                        match title_menu.choice() {
+                            "Continue" => {
+                                slots = build_slot_menu(&storage, &defs);
+                                saving = false;
+                                mode = GameMode::Slots;
+                            }
+                            "Play" => {
+                                stdout().execute(Clear(ClearType::All)).unwrap();
+                                stdout().execute(cursor::RestorePosition).unwrap();
+                                mode = GameMode::Playing;
+                            }
+                            "Campaign" => {
+                                game_mode = CAMPAIGN;
+                                game = Game::for_mode(new_seed(), defs.clone(), game_mode);
+                                replay = Replay::new(game.seed);
+                                replay.mode = game_mode.to_string();
+                                highscore = leaderboard.best(game_mode);
+                                ghost = None;
+                                stdout().execute(Clear(ClearType::All)).unwrap();
+                                stdout().execute(cursor::RestorePosition).unwrap();
+                                mode = GameMode::Playing;
+                            }
+                            "Watch last run" => {
+                                // Only if there is one
+                                if let Ok(last) = Replay::load(&replay_file) {
+                                    stdout().execute(Clear(ClearType::All)).unwrap();
+                                    viewer = Some(ReplayViewer::new(last, defs.clone()));
+                                    mode = GameMode::Replay;
+                                }
+                            }
+                            "Highscores" => {
+                                scores_mode = MODES.iter().position(|m| *m == game_mode).unwrap();
+                                mode = GameMode::Scores;
+                            }
+                            _ => {
+                                disable_raw_mode().unwrap();
+                                execute!(stdout(), LeaveAlternateScreen).unwrap();
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            GameMode::Replay => {
+                let v = viewer.as_mut().unwrap();
+                v.update();
+
+                // Show the replayed run with its live stats and playback controls
+                stdout().queue(cursor::RestorePosition).unwrap();
+                v.game.map.display_all(
+                    &[v.game.trail_layer()],
+                    &[
+                        v.game.flash_layer(),
+                        v.game.shockwave_layer(),
+                        v.game.hitbox_layer(),
+                    ],
+                );
+                redraw(&v.game, highscore);
+                v.display_controls();
+
+                if poll(Duration::from_millis(TICK_MS as u64)).unwrap() {
+                    if let Event::Key(event) = read().unwrap() {
+                        if !v.handle_key(event.code) {
+                            viewer = None;
+                            stdout().execute(Clear(ClearType::All)).unwrap();
+                            mode = GameMode::Title;
+                        }
+                    }
+                }
+            }
+            GameMode::Slots => {
+                display_slots(if saving { "SAVE RUN" } else { "CONTINUE" }, &slots);
+                if let Some(error) = &save_error {
+                    print_text(HEIGHT - 5, error);
+                }
+
+                // Up/Down and Enter to pick a slot
+                // Esc to go back
+                if let Event::Key(event) = read().unwrap() {
+                    let mut back = false;
+                    if let KeyCode::Esc = event.code {
+                        save_error = None;
+                        back = true;
+                    } else if slots.handle_key(event.code) && saving {
+                        let run = SavedRun {
+                            game,
+                            replay,
+                            mode: game_mode.to_string(),
+                        };
+                        if let Err(e) = save::save(&storage, slots.selected, &run) {
+                            // Go back to the pause menu so the run isn't lost
+                            save_error = Some(format!("Could not save:\n{}", e));
+                            game = run.game;
+                            replay = run.replay;
+                            back = true;
+                        } else {
+                            disable_raw_mode().unwrap();
+                            execute!(stdout(), LeaveAlternateScreen).unwrap();
+                            break;
+                        }
+                    } else if let KeyCode::Enter = event.code {
+                        // Resuming a run uses up its slot
+                        match save::load(&storage, slots.selected, defs.clone()) {
+                            Err(e) => {
+                                save_error = Some(format!("Could not load:\n{}", e));
+                            }
+                            Ok(run) => {
+                                if let Err(e) = save::delete(&storage, slots.selected) {
+                                    save_error = Some(format!("Could not resume:\n{}", e));
+                                    continue;
+                                }
+                                game_mode =
+                                    MODES.iter().find(|m| **m == run.mode).unwrap_or(&MODES[0]);
+                                game = run.game;
+                                replay = run.replay;
+
+                                highscore = leaderboard.best(game_mode).max(game.score);
+                                ghost = if options.ghost && game_mode != CAMPAIGN {
+                                    Ghost::load(&storage, game.seed, defs.clone())
+                                } else {
+                                    None
+                                };
+                                if let Some(g) = ghost.as_mut() {
+                                    g.catch_up(game.tick);
+                                }
+                                save_error = None;
+                                stdout().execute(Clear(ClearType::All)).unwrap();
+                                mode = GameMode::Playing;
+                            }
+                        }
+                    }
+                    if back {
+                        stdout().execute(Clear(ClearType::All)).unwrap();
+                        if saving {
+                            // Redraw the paused game
+                            stdout().execute(cursor::RestorePosition).unwrap();
+                            game.map.display();
+                            redraw(&game, highscore);
+                            mode = GameMode::Pause;
+                        } else {
+                            title_menu = build_title_menu(&storage, &defs);
+                            mode = GameMode::Title;
+                        }
+                    }
+                }
+            }
+            GameMode::Scores => {
+                display_scores(&leaderboard, MODES[scores_mode]);
+
+                // Left/Right to change mode
+                // Esc to return to title
+                if let Event::Key(event) = read().unwrap() {
+                    match event.code {
+                        KeyCode::Left => {
+                            scores_mode = (scores_mode + MODES.len() - 1) % MODES.len()
+                        }
+                        KeyCode::Right => scores_mode = (scores_mode + 1) % MODES.len(),
+                        KeyCode::Esc => {
+                            stdout().execute(Clear(ClearType::All)).unwrap();
+                            mode = GameMode::Title;
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            GameMode::GameOver => {
+                // Display game over text
+                stdout()
+                    .queue(cursor::MoveTo((WIDTH / 4 + 2) as u16, (HEIGHT / 2) as u16))
+                    .unwrap();
+                print!(
+                    "{}",
+                    if game.cleared() {
+                        "ALL CLEAR"
+                    } else {
+                        "GAME OVER"
+                    }
+                );
+
+                // Ask for a name if the run made the leaderboard
+                if let Some(input) = name_entry.as_mut() {
+                    stdout()
+                        .queue(cursor::MoveTo((WIDTH / 4) as u16, (HEIGHT / 2 + 1) as u16))
+                        .unwrap();
+                    print!("NEW HIGHSCORE!");
+                    stdout()
+                        .queue(cursor::MoveTo((WIDTH / 8) as u16, (HEIGHT / 2 + 2) as u16))
+                        .unwrap();
+                    print!("Name: ");
+                    input.display(((WIDTH / 8 + 6) as u16, (HEIGHT / 2 + 2) as u16));
+                    stdout().flush().unwrap();
+
+                    // Enter to save
+                    // Esc to skip
+                    if let Event::Key(event) = read().unwrap() {
+                        let submitted = input.handle_key(event.code);
+                        if submitted {
+                            leaderboard.insert(Entry {
+                                name: input.text.trim().to_string(),
+                                score: game.score,
+                                date: leaderboard::today(),
+                                duration: game.tick * TICK_MS as u64 / 1000,
+                                seed: game.seed,
+                                mode: game_mode.to_string(),
+                            });
+                            if let Err(e) = leaderboard.save(&leaderboard_file) {
+                                save_error = Some(format!("Could not save highscore:\n{}", e));
+                            }
+                        }
+                        if submitted || event.code == KeyCode::Esc {
+                            name_entry = None;
+                            stdout().execute(Clear(ClearType::All)).unwrap();
+                            stdout().execute(cursor::RestorePosition).unwrap();
+                            game.map.display();
+                            redraw(&game, highscore);
+                        }
+                    }
+                    continue;
+                }
+
+                stdout()
+                    .queue(cursor::MoveTo((WIDTH / 8) as u16, (HEIGHT / 2 + 1) as u16))
+                    .unwrap();
+                print!("Press ENTER to play again");
+                if let Some(error) = &save_error {
+                    print_text(HEIGHT / 2 + 3, error);
+                }
+                stdout().flush().unwrap();
+
+                // Esc to quit
+                // Enter to play again
+                let key_pressed;
+                if let Event::Key(event) = read().unwrap() {
+                    key_pressed = event;
+                    if let KeyCode::Esc = key_pressed.code {
+                        disable_raw_mode().unwrap();
+                        execute!(stdout(), LeaveAlternateScreen).unwrap();
+                        break;
+                    }
+                    if let KeyCode::Enter = key_pressed.code {
+                        stdout().execute(Clear(ClearType::All)).unwrap();
+                        save_error = None;
+                        mode = GameMode::Playing;
+
+                        // Reset Game, staying in the campaign if playing it
+                        if game_mode != CAMPAIGN {
+                            game_mode = default_mode;
+                        }
+                        game = Game::for_mode(new_seed(), defs.clone(), game_mode);
+                        replay = Replay::new(game.seed);
+                        replay.mode = game_mode.to_string();
+                        highscore = leaderboard.best(game_mode);
+                        ghost = if options.ghost && game_mode != CAMPAIGN {
+                            Ghost::load(&storage, game.seed, defs.clone())
+                        } else {
+                            None
+                        };
+                        stdout().execute(cursor::RestorePosition).unwrap();
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::f64::consts::PI;
+use text_engine::{Enemy, Map, Vec2};
+
+/// A way for enemies to move, run once each time an enemy's move timer is up
+///
+/// Each run picks the next point for the enemy to glide to before its timer
+/// is up again. Patterns keep any state they need in the enemy's `moves`,
+/// `heading` and `anchor` fields, so one pattern can be shared by every enemy
+/// of a type.
+pub trait Movement {
+    /// Returns the point an enemy moves to next
+    ///
+    /// The enemy is always on a whole cell when this is called. The returned
+    /// point can be past the bottom of the map, which means the enemy got
+    /// through.
+    ///
+    /// # Arguments
+    /// * enemy: the enemy to move
+    /// * player: the player's position
+    /// * map: the current map, for its walls
+    fn next(&self, enemy: &mut Enemy, player: Vec2, map: &Map) -> Vec2;
+}
+
+/// Returns a movement pattern from its description in a data file
+///
+/// The description is the pattern's name, then any of its settings in order,
+/// e.g. `sine 3 12`. Settings left out use their defaults.
+///
+/// # Arguments
+/// * text: the description
+pub fn parse(text: &str) -> Result<Box<dyn Movement>, String> {
+    let mut parts = text.split_whitespace();
+    let name = parts.next().unwrap_or("straight");
+    let args = parts
+        .map(|a| a.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid settings for {} movement", name))?;
+    let arg = |i: usize, default: usize| args.get(i).copied().unwrap_or(default).max(1);
+
+    let (movement, count): (Box<dyn Movement>, usize) = match name {
+        "straight" => (Box::new(Straight), 0),
+        "zigzag" => (Box::new(Zigzag { step: arg(0, 1) }), 1),
+        "sine" => (
+            Box::new(Sine {
+                amplitude: arg(0, 3) as f64,
+                period: arg(1, 12) as f64,
+            }),
+            2,
+        ),
+        "dive" => (
+            Box::new(Dive {
+                delay: arg(0, 6) as u64,
+                rows: arg(1, 2) as f64,
+            }),
+            2,
+        ),
+        "strafe" => (
+            Box::new(Strafe {
+                across: arg(0, 8) as u64,
+                drop: arg(1, 2) as u64,
+            }),
+            2,
+        ),
+        "homing" => (
+            Box::new(Homing {
+                turn: arg(0, 3) as u64,
+            }),
+            1,
+        ),
+        _ => return Err(format!("unknown movement {}", name)),
+    };
+    if args.len() > count {
+        return Err(format!("too many settings for {} movement", name));
+    }
+    Ok(movement)
+}
+
+/// Returns true if an enemy at a position would overlap a wall
+///
+/// Rows above or below the map are not checked, so enemies can enter from the
+/// top.
+///
+/// # Arguments
+/// * enemy: the enemy to check
+/// * x: the enemy's left column
+/// * map: the current map
+fn hits_wall(enemy: &Enemy, x: i32, map: &Map) -> bool {
+    let (width, height) = map.size();
+    let right = x + enemy.size.0 as i32 - 1;
+    if x < 1 || right > width as i32 - 2 {
+        return true;
+    }
+    let top = enemy.pos.cell().1;
+    (top.max(0)..(top + enemy.size.1 as i32).min(height as i32)).any(|row| {
+        map.get((x as usize, row as usize)) == '#' || map.get((right as usize, row as usize)) == '#'
+    })
+}
+
+/// Returns the column an enemy reaches by moving sideways along its heading,
+/// turning around at walls
+///
+/// # Arguments
+/// * enemy: the enemy to move
+/// * step: how many columns to move
+/// * map: the current map
+fn sideways(enemy: &mut Enemy, step: f64, map: &Map) -> f64 {
+    for _ in 0..2 {
+        let x = enemy.pos.x + enemy.heading as f64 * step;
+        if !hits_wall(enemy, x as i32, map) {
+            return x;
+        }
+        enemy.heading = -enemy.heading;
+    }
+    enemy.pos.x
+}
+
+/// Returns one column closer to a target, lining up the enemy's middle with it
+///
+/// # Arguments
+/// * enemy: the enemy to move
+/// * target: the column to head for
+/// * map: the current map
+fn towards(enemy: &Enemy, target: f64, map: &Map) -> f64 {
+    let middle = enemy.pos.x + (enemy.size.0 / 2) as f64;
+    let x = if middle < target {
+        enemy.pos.x + 1.0
+    } else if middle > target {
+        enemy.pos.x - 1.0
+    } else {
+        enemy.pos.x
+    };
+    if hits_wall(enemy, x as i32, map) {
+        enemy.pos.x
+    } else {
+        x
+    }
+}
+
+/// Down one row per move
+struct Straight;
+impl Movement for Straight {
+    fn next(&self, enemy: &mut Enemy, _player: Vec2, _map: &Map) -> Vec2 {
+        enemy.pos + Vec2::new(0.0, 1.0)
+    }
+}
+
+/// Down and sideways each move, bouncing off walls
+struct Zigzag {
+    step: usize, // Columns moved sideways per move
+}
+impl Movement for Zigzag {
+    fn next(&self, enemy: &mut Enemy, _player: Vec2, map: &Map) -> Vec2 {
+        Vec2::new(sideways(enemy, self.step as f64, map), enemy.pos.y + 1.0)
+    }
+}
+
+/// Down, drifting from side to side around the spawn column
+struct Sine {
+    amplitude: f64, // Widest drift in columns
+    period: f64,    // Moves per full wave
+}
+impl Movement for Sine {
+    fn next(&self, enemy: &mut Enemy, _player: Vec2, map: &Map) -> Vec2 {
+        let phase = 2.0 * PI * (enemy.moves + 1) as f64 / self.period;
+        let x = (enemy.anchor.x + self.amplitude * phase.sin()).round();
+        if hits_wall(enemy, x as i32, map) {
+            enemy.pos + Vec2::new(0.0, 1.0)
+        } else {
+            Vec2::new(x, enemy.pos.y + 1.0)
+        }
+    }
+}
+
+/// The dive an enemy makes at the player once it peels off a formation,
+/// whatever its type usually does
+pub const PEEL_DIVE: Dive = Dive {
+    delay: 0,
+    rows: 2.0,
+};
+
+/// Down for a while, then a fast dive at where the player was
+pub struct Dive {
+    delay: u64, // Moves before diving
+    rows: f64,  // Rows per move while diving
+}
+impl Movement for Dive {
+    fn next(&self, enemy: &mut Enemy, player: Vec2, map: &Map) -> Vec2 {
+        if enemy.moves < self.delay {
+            return enemy.pos + Vec2::new(0.0, 1.0);
+        }
+        // Lock on to the player's position when the dive starts
+        if enemy.moves == self.delay {
+            enemy.anchor = player;
+        }
+        Vec2::new(towards(enemy, enemy.anchor.x, map), enemy.pos.y + self.rows)
+    }
+}
+
+/// Sideways across the screen, then down a few rows, then back the other way
+struct Strafe {
+    across: u64, // Moves spent going sideways
+    drop: u64,   // Rows down between passes
+}
+impl Movement for Strafe {
+    fn next(&self, enemy: &mut Enemy, _player: Vec2, map: &Map) -> Vec2 {
+        let phase = enemy.moves % (self.across + self.drop);
+        if phase < self.across {
+            return Vec2::new(sideways(enemy, 1.0, map), enemy.pos.y);
+        }
+        if phase == self.across + self.drop - 1 {
+            enemy.heading = -enemy.heading;
+        }
+        enemy.pos + Vec2::new(0.0, 1.0)
+    }
+}
+
+/// Down, slowly turning to follow the player
+struct Homing {
+    turn: u64, // Moves between each column turned
+}
+impl Movement for Homing {
+    fn next(&self, enemy: &mut Enemy, player: Vec2, map: &Map) -> Vec2 {
+        let x = if (enemy.moves + 1).is_multiple_of(self.turn) {
+            towards(enemy, player.x, map)
+        } else {
+            enemy.pos.x
+        };
+        Vec2::new(x, enemy.pos.y + 1.0)
+    }
+}
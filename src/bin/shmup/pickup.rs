@@ -0,0 +1,121 @@
+use text_engine::Vec2;
+
+/// What a pickup does when the player collects it
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Weapon,     // Swaps to the weapon it carries, or upgrades it if already held
+    Heal,       // Restores health, going past full as an overshield
+    Shield,     // Absorbs every hit for a while
+    Bomb,       // Adds a bomb to the player's stock
+    Multiplier, // Multiplies points scored for a while
+    Speed,      // Moves the player further with each step for a while
+    Drone,      // Adds an option drone
+}
+impl Kind {
+    /// Every kind of pickup, in the order of their names in data files
+    pub const ALL: [Kind; 7] = [
+        Kind::Weapon,
+        Kind::Heal,
+        Kind::Shield,
+        Kind::Bomb,
+        Kind::Multiplier,
+        Kind::Speed,
+        Kind::Drone,
+    ];
+
+    /// Returns the name of the kind, as used in data files and saves
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Weapon => "weapon",
+            Kind::Heal => "heal",
+            Kind::Shield => "shield",
+            Kind::Bomb => "bomb",
+            Kind::Multiplier => "multiplier",
+            Kind::Speed => "speed",
+            Kind::Drone => "drone",
+        }
+    }
+
+    /// Returns the kind with a name, if there is one
+    ///
+    /// # Arguments
+    /// * name: the kind's name
+    pub fn find(name: &str) -> Option<Self> {
+        Kind::ALL.iter().copied().find(|k| k.name() == name)
+    }
+
+    /// Returns the character the pickup is drawn with
+    pub fn symbol(self) -> char {
+        match self {
+            Kind::Weapon => 'P',
+            Kind::Heal => 'H',
+            Kind::Shield => 'S',
+            Kind::Bomb => 'B',
+            Kind::Multiplier => '$',
+            Kind::Speed => 'F',
+            Kind::Drone => 'D',
+        }
+    }
+}
+
+/// One entry in an enemy type's drop table
+pub struct DropRate {
+    pub kind: Kind,
+    pub chance: u32, // Percent chance of dropping it
+}
+
+/// Reads one entry of a drop table from its description in a data file
+///
+/// The description is the kind of pickup, then the percent chance of
+/// dropping it, e.g. `heal 10`.
+///
+/// # Arguments
+/// * text: the description
+pub fn parse_drop(text: &str) -> Result<DropRate, String> {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    let kind = parts.first().copied().unwrap_or("");
+    let kind = Kind::find(kind).ok_or_else(|| format!("unknown pickup {}", kind))?;
+    match parts.get(1).map(|c| c.parse()) {
+        Some(Ok(chance)) if chance <= 100 && parts.len() == 2 => Ok(DropRate { kind, chance }),
+        _ => Err(format!("invalid drop {}", text)),
+    }
+}
+
+/// A pickup drifting down the map
+#[derive(Clone)]
+pub struct Pickup {
+    pub pos: Vec2,
+    pub kind: Kind,
+    pub weapon: usize, // Index of the weapon it carries, for weapon pickups
+}
+
+/// Timed effects of pickups on the player, in milliseconds left
+#[derive(Clone, Default)]
+pub struct Effects {
+    pub shield: u128,
+    pub multiplier: u128,
+    pub speed: u128,
+}
+impl Effects {
+    /// Counts down every effect
+    ///
+    /// # Arguments
+    /// * ms: milliseconds passed
+    pub fn tick(&mut self, ms: u128) {
+        self.shield = self.shield.saturating_sub(ms);
+        self.multiplier = self.multiplier.saturating_sub(ms);
+        self.speed = self.speed.saturating_sub(ms);
+    }
+
+    /// Returns the name and time left of each effect still going
+    pub fn active(&self) -> Vec<(&'static str, u128)> {
+        vec![
+            ("SHIELD", self.shield),
+            ("MULTIPLIER", self.multiplier),
+            ("SPEED", self.speed),
+        ]
+        .into_iter()
+        .filter(|(_, time)| *time > 0)
+        .collect()
+    }
+}
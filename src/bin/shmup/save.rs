@@ -0,0 +1,121 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::rc::Rc;
+use text_engine::replay::Replay;
+use text_engine::storage::{self, Storage};
+
+use crate::enemy::Registry;
+use crate::game::{Game, TICK_MS};
+
+/// Number of save slots
+pub const SLOTS: usize = 3;
+
+// Current version of the save file format. Raising it turns away every
+// existing save in `load`, so only do so for a released format change, and
+// migrate the older version there if it can be
+const VERSION: u32 = 1;
+
+/// A suspended run, with everything needed to pick it up again
+pub struct SavedRun {
+    pub game: Game,
+    pub replay: Replay, // Recording of the run so far
+    pub mode: String,   // Game mode the run counts towards
+}
+
+/// Returns the file for a save slot
+///
+/// # Arguments
+/// * storage: where save data is kept
+/// * slot: the slot number, starting at 0
+fn slot_path(storage: &Storage, slot: usize) -> PathBuf {
+    storage.data_path(&format!("saves/slot-{}.sav", slot + 1))
+}
+
+/// Writes a run to a save slot, replacing whatever was there
+///
+/// # Arguments
+/// * storage: where save data is kept
+/// * slot: the slot number, starting at 0
+/// * run: the run to save
+pub fn save(storage: &Storage, slot: usize, run: &SavedRun) -> io::Result<()> {
+    let body = format!(
+        "mode {}\n{}replay\n{}",
+        run.mode,
+        run.game.write_state(),
+        run.replay.write()
+    );
+    storage::write_versioned(slot_path(storage, slot), "save", VERSION, &body)
+}
+
+/// Reads the run in a save slot
+///
+/// # Arguments
+/// * storage: where save data is kept
+/// * slot: the slot number, starting at 0
+/// * defs: the enemy types the run can use
+pub fn load(storage: &Storage, slot: usize, defs: Rc<Registry>) -> io::Result<SavedRun> {
+    let (version, body) = storage::read_versioned(slot_path(storage, slot), "save", VERSION)?
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "save slot is empty"))?;
+    let invalid = || io::Error::new(ErrorKind::InvalidData, "malformed save file");
+
+    // No older version has been released. Any that is met was played under
+    // different rules, so it is turned away rather than continued wrongly
+    if version < VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "save file is from an older version",
+        ));
+    }
+
+    // The mode line, then the run state, then the recording so far
+    let (mode, rest) = body.split_at(body.find('\n').ok_or_else(invalid)?);
+    let mode = mode.strip_prefix("mode ").ok_or_else(invalid)?;
+    let split = rest.find("\nreplay\n").ok_or_else(invalid)?;
+    Ok(SavedRun {
+        game: Game::read_state(&rest[1..split + 1], defs)?,
+        replay: Replay::read(&rest[split + "\nreplay\n".len()..])?,
+        mode: mode.to_string(),
+    })
+}
+
+/// Empties a save slot
+///
+/// # Arguments
+/// * storage: where save data is kept
+/// * slot: the slot number, starting at 0
+pub fn delete(storage: &Storage, slot: usize) -> io::Result<()> {
+    match fs::remove_file(slot_path(storage, slot)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Returns a one-line description of each slot, None for empty slots
+///
+/// # Arguments
+/// * storage: where save data is kept
+/// * defs: the enemy types the run can use
+pub fn summaries(storage: &Storage, defs: &Rc<Registry>) -> Vec<Option<String>> {
+    (0..SLOTS)
+        .map(|slot| {
+            let path = slot_path(storage, slot);
+            if !path.exists() {
+                return None;
+            }
+            Some(match load(storage, slot, defs.clone()) {
+                Ok(run) => {
+                    let secs = run.game.tick * TICK_MS as u64 / 1000;
+                    format!(
+                        "{} {} {}:{:02}",
+                        run.mode,
+                        run.game.score,
+                        secs / 60,
+                        secs % 60
+                    )
+                }
+                Err(_) => "unreadable".to_string(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,49 @@
+use text_engine::Vec2;
+
+use crate::{COMBO_DECAY, COMBO_STEP, COMBO_WINDOW, MAX_COMBO_MULTIPLIER};
+
+/// A run of kills in quick succession, which multiplies the points scored
+///
+/// The combo holds for `COMBO_WINDOW` milliseconds after each kill, then
+/// loses a kill every `COMBO_DECAY` milliseconds until the next one.
+#[derive(Clone, Default)]
+pub struct Combo {
+    pub count: usize, // Kills in the combo
+    pub timer: u128,  // Milliseconds since the last kill, or since it last decayed
+}
+impl Combo {
+    /// Adds a kill to the combo and starts its window again
+    pub fn kill(&mut self) {
+        self.count += 1;
+        self.timer = 0;
+    }
+
+    /// Lets time pass, decaying the combo once its window is over
+    ///
+    /// # Arguments
+    /// * ms: milliseconds passed
+    pub fn tick(&mut self, ms: u128) {
+        if self.count == 0 {
+            return;
+        }
+        self.timer += ms;
+        if self.timer >= COMBO_WINDOW {
+            self.count -= 1;
+            self.timer = COMBO_WINDOW - COMBO_DECAY;
+        }
+    }
+
+    /// Returns what the combo multiplies points by, going up by one every
+    /// `COMBO_STEP` kills
+    pub fn multiplier(&self) -> i32 {
+        (1 + self.count / COMBO_STEP).min(MAX_COMBO_MULTIPLIER) as i32
+    }
+}
+
+/// Points scored, floating up from where they were earned
+#[derive(Clone)]
+pub struct Popup {
+    pub pos: Vec2,
+    pub text: String,
+    pub time: u128, // Milliseconds left to show it
+}
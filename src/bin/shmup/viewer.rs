@@ -0,0 +1,236 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    terminal::{Clear, ClearType},
+    ExecutableCommand, QueueableCommand,
+};
+use std::io::stdout;
+use std::rc::Rc;
+use std::time::Instant;
+use text_engine::replay::{Replay, Snapshots};
+
+use crate::enemy::Registry;
+use crate::game::{Game, TICK_MS};
+use crate::{HEIGHT, WIDTH};
+
+// Playback speeds, selected with +/-
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const NORMAL_SPEED: usize = 2;
+
+const SNAPSHOT_TICKS: u64 = 100; // Ticks between seek snapshots
+const SEEK_TICKS: u64 = 100; // Ticks skipped by the left/right arrows
+
+/// Replay viewer scene: plays back a recorded run with seeking and speed control
+pub struct ReplayViewer {
+    pub game: Game,
+    replay: Replay,
+    snapshots: Snapshots<Game>,
+    speed: usize,
+    paused: bool,
+    pending: f64, // Ticks owed to the current playback speed
+    last_frame: Instant,
+}
+impl ReplayViewer {
+    /// Returns a viewer paused at the start of a replay
+    ///
+    /// The replay is simulated once up front to collect seek snapshots.
+    ///
+    /// # Arguments
+    /// * replay: the run to view
+    /// * defs: the enemy types the run can use
+    pub fn new(replay: Replay, defs: Rc<Registry>) -> Self {
+        let mut game = Game::for_mode(replay.seed, defs.clone(), &replay.mode);
+        let mut snapshots = Snapshots::new(SNAPSHOT_TICKS);
+        snapshots.capture(0, &game);
+        while game.tick < replay.length && !game.is_over() {
+            game.step_recorded(&replay);
+            snapshots.capture(game.tick, &game);
+        }
+
+        Self {
+            game: Game::for_mode(replay.seed, defs, &replay.mode),
+            replay,
+            snapshots,
+            speed: NORMAL_SPEED,
+            paused: true,
+            pending: 0.0,
+            last_frame: Instant::now(),
+        }
+    }
+
+    /// Returns true once playback has reached the end of the replay
+    fn at_end(&self) -> bool {
+        self.game.tick >= self.replay.length || self.game.is_over()
+    }
+
+    /// Plays the ticks due since the last call at the current speed
+    pub fn update(&mut self) {
+        let elapsed = self.last_frame.elapsed();
+        self.last_frame = Instant::now();
+        if self.paused {
+            return;
+        }
+
+        self.pending += elapsed.as_secs_f64() * 1000.0 * SPEEDS[self.speed] / TICK_MS as f64;
+        while self.pending >= 1.0 && !self.at_end() {
+            self.game.step_recorded(&self.replay);
+            self.pending -= 1.0;
+        }
+        if self.at_end() {
+            self.paused = true;
+        }
+    }
+
+    /// Jumps to a tick, starting from the closest snapshot before it
+    ///
+    /// # Arguments
+    /// * tick: the tick to show, clamped to the length of the replay
+    pub fn seek(&mut self, tick: u64) {
+        let tick = tick.min(self.replay.length);
+        if let Some((at, snapshot)) = self.snapshots.before(tick) {
+            if tick < self.game.tick || at > self.game.tick {
+                self.game = snapshot.clone();
+            }
+        }
+        while self.game.tick < tick && !self.game.is_over() {
+            self.game.step_recorded(&self.replay);
+        }
+        self.pending = 0.0;
+    }
+
+    /// Responds to a key press, returning false when the viewer should close
+    ///
+    /// Space pauses, +/- change speed, . and , step one tick forwards or
+    /// back, the arrows seek, 0-9 jump to a tenth of the run, Esc exits.
+    ///
+    /// # Arguments
+    /// * code: the key pressed
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Esc => return false,
+            KeyCode::Char(' ') => {
+                if self.at_end() {
+                    self.seek(0);
+                }
+                self.paused = !self.paused;
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+            }
+            KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+            KeyCode::Char('.') => {
+                self.paused = true;
+                self.seek(self.game.tick + 1);
+            }
+            KeyCode::Char(',') => {
+                self.paused = true;
+                self.seek(self.game.tick.saturating_sub(1));
+            }
+            KeyCode::Right => self.seek(self.game.tick + SEEK_TICKS),
+            KeyCode::Left => self.seek(self.game.tick.saturating_sub(SEEK_TICKS)),
+            KeyCode::Home => self.seek(0),
+            KeyCode::End => self.seek(self.replay.length),
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                let tenths = c.to_digit(10).unwrap() as u64;
+                self.seek(self.replay.length * tenths / 10);
+            }
+            _ => (),
+        }
+        true
+    }
+
+    /// Draws the seek bar and playback state under the map
+    pub fn display_controls(&self) {
+        let width = (WIDTH - 2) as u64;
+        let filled = self.game.tick * width / self.replay.length.max(1);
+
+        let mut bar = String::from("[");
+        for i in 0..width {
+            bar.push(if i < filled { '=' } else { '-' });
+        }
+        bar.push(']');
+
+        stdout().queue(cursor::MoveTo(0, HEIGHT as u16)).unwrap();
+        stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+        print!(
+            "{} {}/{}",
+            bar,
+            format_time(self.game.tick),
+            format_time(self.replay.length)
+        );
+        stdout()
+            .queue(cursor::MoveTo(0, (HEIGHT + 1) as u16))
+            .unwrap();
+        stdout().queue(Clear(ClearType::UntilNewLine)).unwrap();
+        print!(
+            "{:>5}x {:<6}  SPACE pause  +/- speed  ,/. step  </> seek  ESC exit",
+            SPEEDS[self.speed],
+            if self.paused { "PAUSED" } else { "PLAY" }
+        );
+        stdout().execute(cursor::RestorePosition).unwrap();
+    }
+}
+
+/// Formats a tick count as minutes and seconds
+///
+/// # Arguments
+/// * tick: the tick count to format
+fn format_time(tick: u64) -> String {
+    let tenths = tick * TICK_MS as u64 / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Command;
+
+    // A replay of a run that moves and shoots from side to side
+    fn recorded() -> (Replay, Rc<Registry>) {
+        let defs = Rc::new(Registry::builtin());
+        let mut replay = Replay::new(3);
+        for tick in 0..SNAPSHOT_TICKS * 3 {
+            let side = if tick / 20 % 2 == 0 {
+                Command::Left
+            } else {
+                Command::Right
+            };
+            replay.record(tick, side.to_char());
+            replay.record(tick, Command::Fire.to_char());
+        }
+        (replay, defs)
+    }
+
+    // The state a straight playthrough reaches at a tick
+    fn played_to(replay: &Replay, defs: Rc<Registry>, tick: u64) -> String {
+        let mut game = Game::for_mode(replay.seed, defs, &replay.mode);
+        while game.tick < tick && !game.is_over() {
+            game.step_recorded(replay);
+        }
+        game.write_state()
+    }
+
+    #[test]
+    fn seek_matches_playing_through() {
+        let (replay, defs) = recorded();
+        let mut viewer = ReplayViewer::new(replay, defs.clone());
+        for tick in [250, 120, 5, 299] {
+            viewer.seek(tick);
+            assert_eq!(viewer.game.tick, tick);
+            assert_eq!(
+                viewer.game.write_state(),
+                played_to(&viewer.replay, defs.clone(), tick)
+            );
+        }
+    }
+
+    #[test]
+    fn seek_stops_at_the_end() {
+        let (replay, defs) = recorded();
+        let length = replay.length;
+        let mut viewer = ReplayViewer::new(replay, defs);
+        viewer.seek(length + 100);
+        assert!(viewer.at_end());
+        assert!(viewer.game.tick <= length);
+    }
+}
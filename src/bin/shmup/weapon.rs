@@ -0,0 +1,329 @@
+use text_engine::Vec2;
+
+/// Highest level a weapon can be upgraded to
+pub const MAX_LEVEL: usize = 3;
+
+/// Heat at which a weapon overheats and stops firing until it cools down
+pub const MAX_HEAT: u32 = 100;
+
+// Heat a weapon loses every second
+const COOLING: u32 = 50;
+
+/// Milliseconds fire has to be held for a fully charged shot
+pub const CHARGE_TIME: u128 = 1500;
+
+/// Milliseconds fire has to be held for letting go to fire a charged shot
+pub const CHARGE_MIN: u128 = 500;
+
+// Damage of each bullet in a fully charged shot
+const CHARGE_DAMAGE: usize = 4;
+
+/// Every weapon the player can carry, the first being the one runs start with
+pub const WEAPONS: [&dyn Weapon; 6] = [&Single, &Double, &Spread, &Piercing, &Rapid, &Laser];
+
+/// One bullet fired by a player's weapon
+pub struct Shot {
+    pub offset: i32,   // Columns to the right of the player it starts in
+    pub angle: f64,    // Degrees from straight up, positive to the right
+    pub damage: usize, // Health it takes from whatever it hits
+    pub pierce: usize, // Enemies it can pass through after destroying them
+}
+impl Shot {
+    /// Returns a shot that does 1 damage and stops at the first thing it hits
+    ///
+    /// # Arguments
+    /// * offset: columns to the right of the player it starts in
+    /// * angle: degrees from straight up, positive to the right
+    pub fn new(offset: i32, angle: f64) -> Self {
+        Self {
+            offset,
+            angle,
+            damage: 1,
+            pierce: 0,
+        }
+    }
+
+    /// Returns the direction the shot travels in, one cell long
+    pub fn direction(&self) -> Vec2 {
+        let radians = self.angle.to_radians();
+        Vec2::new(radians.sin(), -radians.cos())
+    }
+}
+
+/// A kind of gun the player can carry
+///
+/// Weapons hold no state of their own, so they can be shared by every run.
+/// How far a gun has cooled down is kept in the `Gun` carrying it. Levels go
+/// from 1 to `MAX_LEVEL`.
+pub trait Weapon {
+    /// Returns the weapon's name, as shown in the sidebar and saves
+    fn name(&self) -> &'static str;
+
+    /// Returns the milliseconds between shots
+    ///
+    /// # Arguments
+    /// * level: the weapon's level
+    fn cooldown(&self, level: usize) -> u128;
+
+    /// Returns the heat each shot adds, out of `MAX_HEAT`
+    ///
+    /// # Arguments
+    /// * level: the weapon's level
+    fn heat(&self, level: usize) -> u32;
+
+    /// Returns the bullets fired by one shot
+    ///
+    /// # Arguments
+    /// * level: the weapon's level
+    fn shots(&self, level: usize) -> Vec<Shot>;
+
+    /// Returns true if the weapon fires a beam rather than bullets
+    ///
+    /// A beam reaches straight up to whatever is in its way while fire is
+    /// held, and each shot is the damage it does to that when the weapon is
+    /// ready.
+    fn is_beam(&self) -> bool {
+        false
+    }
+}
+
+/// One bullet straight ahead, hitting harder at the top level
+struct Single;
+impl Weapon for Single {
+    fn name(&self) -> &'static str {
+        "single"
+    }
+
+    fn cooldown(&self, level: usize) -> u128 {
+        [250, 200, 150][level - 1]
+    }
+
+    fn heat(&self, _level: usize) -> u32 {
+        5
+    }
+
+    fn shots(&self, level: usize) -> Vec<Shot> {
+        let mut shot = Shot::new(0, 0.0);
+        if level == MAX_LEVEL {
+            shot.damage = 2;
+        }
+        vec![shot]
+    }
+}
+
+/// Two bullets either side of the ship, with a third between them at the
+/// top level
+struct Double;
+impl Weapon for Double {
+    fn name(&self) -> &'static str {
+        "double"
+    }
+
+    fn cooldown(&self, level: usize) -> u128 {
+        [250, 200, 200][level - 1]
+    }
+
+    fn heat(&self, _level: usize) -> u32 {
+        8
+    }
+
+    fn shots(&self, level: usize) -> Vec<Shot> {
+        let mut shots = vec![Shot::new(-1, 0.0), Shot::new(1, 0.0)];
+        if level == MAX_LEVEL {
+            shots.push(Shot::new(0, 0.0));
+        }
+        shots
+    }
+}
+
+/// Three bullets fanning out, then five
+struct Spread;
+impl Weapon for Spread {
+    fn name(&self) -> &'static str {
+        "spread"
+    }
+
+    fn cooldown(&self, level: usize) -> u128 {
+        [300, 300, 220][level - 1]
+    }
+
+    fn heat(&self, _level: usize) -> u32 {
+        10
+    }
+
+    fn shots(&self, level: usize) -> Vec<Shot> {
+        let angles: &[f64] = match level {
+            1 => &[-15.0, 0.0, 15.0],
+            _ => &[-30.0, -15.0, 0.0, 15.0, 30.0],
+        };
+        angles.iter().map(|a| Shot::new(0, *a)).collect()
+    }
+}
+
+/// A heavy bullet that punches through the enemies it destroys
+struct Piercing;
+impl Weapon for Piercing {
+    fn name(&self) -> &'static str {
+        "piercing"
+    }
+
+    fn cooldown(&self, level: usize) -> u128 {
+        [400, 350, 300][level - 1]
+    }
+
+    fn heat(&self, _level: usize) -> u32 {
+        12
+    }
+
+    fn shots(&self, level: usize) -> Vec<Shot> {
+        let mut shot = Shot::new(0, 0.0);
+        shot.damage = 2;
+        shot.pierce = level;
+        vec![shot]
+    }
+}
+
+/// A stream of bullets that overheats quickly
+struct Rapid;
+impl Weapon for Rapid {
+    fn name(&self) -> &'static str {
+        "rapid"
+    }
+
+    fn cooldown(&self, level: usize) -> u128 {
+        [100, 75, 50][level - 1]
+    }
+
+    fn heat(&self, level: usize) -> u32 {
+        [12, 10, 8][level - 1]
+    }
+
+    fn shots(&self, _level: usize) -> Vec<Shot> {
+        vec![Shot::new(0, 0.0)]
+    }
+}
+
+/// A beam that hits the first thing above the ship, overheating quickly
+struct Laser;
+impl Weapon for Laser {
+    fn name(&self) -> &'static str {
+        "laser"
+    }
+
+    fn cooldown(&self, level: usize) -> u128 {
+        [150, 125, 100][level - 1]
+    }
+
+    fn heat(&self, level: usize) -> u32 {
+        [15, 13, 11][level - 1]
+    }
+
+    fn shots(&self, level: usize) -> Vec<Shot> {
+        let mut shot = Shot::new(0, 0.0);
+        if level == MAX_LEVEL {
+            shot.damage = 2;
+        }
+        vec![shot]
+    }
+
+    fn is_beam(&self) -> bool {
+        true
+    }
+}
+
+/// Returns the bullets of a charged shot, three abreast, that pierce and hit
+/// harder the longer fire was held
+///
+/// # Arguments
+/// * charge: milliseconds fire was held, up to `CHARGE_TIME`
+pub fn charged(charge: u128) -> Vec<Shot> {
+    let damage = 1 + (CHARGE_DAMAGE - 1) * charge.min(CHARGE_TIME) as usize / CHARGE_TIME as usize;
+    (-1..=1)
+        .map(|offset| Shot {
+            offset,
+            angle: 0.0,
+            damage,
+            pierce: damage,
+        })
+        .collect()
+}
+
+/// Returns the index of a weapon by name
+///
+/// # Arguments
+/// * name: the weapon's name
+pub fn find(name: &str) -> Option<usize> {
+    WEAPONS.iter().position(|w| w.name() == name)
+}
+
+/// The weapon the player is carrying, and how ready it is to fire
+#[derive(Clone)]
+pub struct Gun {
+    pub weapon: usize,    // Index of the weapon in `WEAPONS`
+    pub level: usize,     // From 1 to `MAX_LEVEL`
+    pub cooldown: u128,   // Milliseconds left before it can fire again
+    pub heat: u32,        // Out of `MAX_HEAT`
+    pub overheated: bool, // Stopped firing until it has cooled right down
+}
+impl Gun {
+    /// Returns a cool gun at the first level
+    ///
+    /// # Arguments
+    /// * weapon: the index of the weapon
+    pub fn new(weapon: usize) -> Self {
+        Self {
+            weapon,
+            level: 1,
+            cooldown: 0,
+            heat: 0,
+            overheated: false,
+        }
+    }
+
+    /// Returns the weapon the gun fires
+    pub fn weapon(&self) -> &'static dyn Weapon {
+        WEAPONS[self.weapon]
+    }
+
+    /// Fires the gun, returning its bullets, or None if it is not ready
+    pub fn fire(&mut self) -> Option<Vec<Shot>> {
+        if self.cooldown > 0 || self.overheated {
+            return None;
+        }
+        let weapon = self.weapon();
+        self.cooldown = weapon.cooldown(self.level);
+        self.heat += weapon.heat(self.level);
+        if self.heat >= MAX_HEAT {
+            self.heat = MAX_HEAT;
+            self.overheated = true;
+        }
+        Some(weapon.shots(self.level))
+    }
+
+    /// Lets the gun cool down for a while
+    ///
+    /// # Arguments
+    /// * ms: milliseconds passed
+    pub fn cool(&mut self, ms: u128) {
+        self.cooldown = self.cooldown.saturating_sub(ms);
+        self.heat = self
+            .heat
+            .saturating_sub((COOLING as u128 * ms / 1000) as u32);
+        if self.heat == 0 {
+            self.overheated = false;
+        }
+    }
+
+    /// Picks up a weapon, upgrading the gun if it is the same one or
+    /// swapping to it at the same level if not
+    ///
+    /// # Arguments
+    /// * weapon: the index of the weapon picked up
+    pub fn pick_up(&mut self, weapon: usize) {
+        if weapon == self.weapon {
+            self.level = (self.level + 1).min(MAX_LEVEL);
+        } else {
+            self.weapon = weapon;
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An error in a data file, with where it happened
+pub struct DataError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+impl DataError {
+    /// Returns a new error
+    ///
+    /// # Arguments
+    /// * file: name of the file the error is in
+    /// * line: line number, starting at 1
+    /// * message: what went wrong
+    pub fn new(file: &str, line: usize, message: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            line,
+            message: message.to_string(),
+        }
+    }
+}
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+impl fmt::Debug for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A `key = value` line in a data file
+///
+/// Values are trimmed unless they are wrapped in double quotes.
+pub struct Field {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+/// A named `[section]` of a data file and the fields under it
+///
+/// Data files look like:
+/// ```text
+/// # Comments start with #
+/// [grunt]
+/// sprite = XX
+/// sprite = XX
+/// health = 1
+/// ```
+pub struct Section {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub fields: Vec<Field>,
+}
+impl Section {
+    /// Returns the raw value of a field, if the section has it
+    ///
+    /// # Arguments
+    /// * key: the field to look up
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|f| f.key == key)
+            .map(|f| f.value.as_str())
+    }
+
+    /// Returns the raw values of every line with a key, in order
+    ///
+    /// # Arguments
+    /// * key: the field to look up
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|f| f.key == key)
+            .map(|f| f.value.as_str())
+            .collect()
+    }
+
+    /// Returns a field parsed as a value, or a default if the section does not have it
+    ///
+    /// # Arguments
+    /// * key: the field to look up
+    /// * default: the value to use if the field is missing
+    pub fn parse_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, DataError> {
+        match self.fields.iter().find(|f| f.key == key) {
+            Some(f) => f.value.parse().map_err(|_| {
+                self.error(f.line, &format!("invalid value for {}: {}", key, f.value))
+            }),
+            None => Ok(default),
+        }
+    }
+
+    /// Returns a field parsed as a value, failing if the section does not have it
+    ///
+    /// # Arguments
+    /// * key: the field to look up
+    pub fn parse<T: FromStr>(&self, key: &str) -> Result<T, DataError> {
+        let f =
+            self.fields.iter().find(|f| f.key == key).ok_or_else(|| {
+                self.error(self.line, &format!("[{}] is missing {}", self.name, key))
+            })?;
+        f.value
+            .parse()
+            .map_err(|_| self.error(f.line, &format!("invalid value for {}: {}", key, f.value)))
+    }
+
+    /// Returns an error located in this section's file
+    ///
+    /// # Arguments
+    /// * line: line number, starting at 1
+    /// * message: what went wrong
+    pub fn error(&self, line: usize, message: &str) -> DataError {
+        DataError::new(&self.file, line, message)
+    }
+
+    /// Returns an error for any field not in a list of known keys
+    ///
+    /// # Arguments
+    /// * known: every key the section may use
+    pub fn check_keys(&self, known: &[&str]) -> Result<(), DataError> {
+        match self
+            .fields
+            .iter()
+            .find(|f| !known.contains(&f.key.as_str()))
+        {
+            Some(f) => Err(self.error(f.line, &format!("unknown field {}", f.key))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Splits a data file into its sections
+///
+/// # Arguments
+/// * file: name of the file, used in errors
+/// * text: contents of the file
+pub fn parse(file: &str, text: &str) -> Result<Vec<Section>, DataError> {
+    let mut sections: Vec<Section> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if !line.ends_with(']') || line.len() < 3 {
+                return Err(DataError::new(file, line_no, "malformed section header"));
+            }
+            let name = line[1..line.len() - 1].trim();
+            if sections.iter().any(|s| s.name == name) {
+                return Err(DataError::new(
+                    file,
+                    line_no,
+                    &format!("duplicate section [{}]", name),
+                ));
+            }
+            sections.push(Section {
+                name: name.to_string(),
+                file: file.to_string(),
+                line: line_no,
+                fields: Vec::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = match line.find('=') {
+            Some(eq) => (line[..eq].trim(), line[eq + 1..].trim()),
+            None => return Err(DataError::new(file, line_no, "expected key = value")),
+        };
+        let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            &value[1..value.len() - 1]
+        } else {
+            value
+        };
+        match sections.last_mut() {
+            Some(section) => section.fields.push(Field {
+                key: key.to_string(),
+                value: value.to_string(),
+                line: line_no,
+            }),
+            None => return Err(DataError::new(file, line_no, "field outside of a section")),
+        }
+    }
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_sections_and_fields() {
+        let text = "# comment\n\
+                    [grunt]\n\
+                    sprite = XX\n\
+                    sprite = XX\n\
+                    name = \" a b \"\n\
+                    \n\
+                    [scout]\n\
+                    health = 2\n";
+        let sections = parse("test.txt", text).unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!((sections[0].name.as_str(), sections[0].line), ("grunt", 2));
+        assert_eq!(sections[0].get_all("sprite"), vec!["XX", "XX"]);
+        assert_eq!(sections[0].get("name"), Some(" a b "));
+        assert_eq!(sections[1].parse::<usize>("health").unwrap(), 2);
+        assert_eq!(sections[1].parse_or("speed", 300).unwrap(), 300);
+    }
+
+    #[test]
+    fn parse_errors_name_file_and_line() {
+        let error = |text: &str| parse("test.txt", text).err().unwrap().to_string();
+        assert_eq!(
+            error("health = 1\n"),
+            "test.txt:1: field outside of a section"
+        );
+        assert_eq!(error("[a]\n\nhealth\n"), "test.txt:3: expected key = value");
+        assert_eq!(error("[a]\n[a\n"), "test.txt:2: malformed section header");
+        assert_eq!(error("[a]\n[a]\n"), "test.txt:2: duplicate section [a]");
+
+        let sections = parse("test.txt", "[a]\nhealth = lots\n").unwrap();
+        assert_eq!(
+            sections[0]
+                .parse::<usize>("health")
+                .err()
+                .unwrap()
+                .to_string(),
+            "test.txt:2: invalid value for health: lots"
+        );
+        assert_eq!(
+            sections[0]
+                .parse::<usize>("speed")
+                .err()
+                .unwrap()
+                .to_string(),
+            "test.txt:1: [a] is missing speed"
+        );
+        assert_eq!(
+            sections[0]
+                .check_keys(&["speed"])
+                .err()
+                .unwrap()
+                .to_string(),
+            "test.txt:2: unknown field health"
+        );
+    }
+}
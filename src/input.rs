@@ -0,0 +1,133 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::time::{Duration, Instant};
+
+/// Keys or modifiers being held down, as far as a terminal can tell
+///
+/// Terminals send a key again and again while it is held, but never say when
+/// it is let go, and modifiers on their own send nothing at all. So anything
+/// seen counts as held until it has not been seen for a while, and is then
+/// reported as released. The wait has to outlast the delay before a held key
+/// starts repeating.
+pub struct Held<K> {
+    gap: Duration,
+    seen: Vec<(K, Instant)>, // Everything seen lately and when it was last seen
+}
+impl<K: Copy + PartialEq> Held<K> {
+    /// Returns a tracker with nothing held
+    ///
+    /// # Arguments
+    /// * gap: milliseconds after last being seen that something stops being held
+    pub fn new(gap: u64) -> Self {
+        Self {
+            gap: Duration::from_millis(gap),
+            seen: Vec::new(),
+        }
+    }
+
+    /// Notes that a key or modifier was just seen
+    ///
+    /// # Arguments
+    /// * key: the key or modifier
+    pub fn press(&mut self, key: K) {
+        self.press_at(key, Instant::now());
+    }
+
+    /// Notes that a key or modifier was seen at a given time
+    ///
+    /// # Arguments
+    /// * key: the key or modifier
+    /// * at: when it was seen
+    pub fn press_at(&mut self, key: K, at: Instant) {
+        self.seen.retain(|s| s.0 != key);
+        self.seen.push((key, at));
+    }
+
+    /// Returns true if a key or modifier has been seen recently enough to
+    /// still be held
+    ///
+    /// # Arguments
+    /// * key: the key or modifier
+    pub fn is_held(&self, key: K) -> bool {
+        self.is_held_at(key, Instant::now())
+    }
+
+    /// Returns true if a key or modifier was still held at a given time
+    ///
+    /// # Arguments
+    /// * key: the key or modifier
+    /// * now: the time to check at
+    pub fn is_held_at(&self, key: K, now: Instant) -> bool {
+        self.seen
+            .iter()
+            .any(|s| s.0 == key && now.saturating_duration_since(s.1) < self.gap)
+    }
+
+    /// Returns the keys or modifiers let go since this was last called
+    pub fn released(&mut self) -> Vec<K> {
+        self.released_at(Instant::now())
+    }
+
+    /// Returns the keys or modifiers let go by a given time since this was
+    /// last called
+    ///
+    /// # Arguments
+    /// * now: the time to check at
+    pub fn released_at(&mut self, now: Instant) -> Vec<K> {
+        let gap = self.gap;
+        let (held, released): (Vec<_>, Vec<_>) = self
+            .seen
+            .drain(..)
+            .partition(|s| now.saturating_duration_since(s.1) < gap);
+        self.seen = held;
+        released.into_iter().map(|s| s.0).collect()
+    }
+}
+
+/// Returns the key pressed with Shift taken off, and whether Shift was held
+///
+/// Some terminals only send a capital letter for Shift and a letter, without
+/// the modifier, so capitals count as shifted too.
+///
+/// # Arguments
+/// * event: the key event read from the terminal
+pub fn unshift(event: KeyEvent) -> (KeyCode, bool) {
+    let shifted = event.modifiers.contains(KeyModifiers::SHIFT);
+    match event.code {
+        KeyCode::Char(c) if c.is_ascii_uppercase() => (KeyCode::Char(c.to_ascii_lowercase()), true),
+        code => (code, shifted),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn held_until_gap_passes() {
+        let start = Instant::now();
+        let mut held = Held::new(50);
+        held.press_at('a', start);
+        assert!(held.is_held_at('a', start + ms(49)));
+        assert!(held.released_at(start + ms(49)).is_empty());
+        assert!(!held.is_held_at('b', start));
+
+        assert!(!held.is_held_at('a', start + ms(50)));
+        assert_eq!(held.released_at(start + ms(60)), vec!['a']);
+        assert!(held.released_at(start + ms(60)).is_empty());
+    }
+
+    #[test]
+    fn press_again_keeps_held() {
+        let start = Instant::now();
+        let mut held = Held::new(50);
+        held.press_at('a', start);
+        held.press_at('a', start + ms(30));
+        assert!(held.is_held_at('a', start + ms(60)));
+        assert!(held.released_at(start + ms(60)).is_empty());
+        assert_eq!(held.released_at(start + ms(80)), vec!['a']);
+    }
+}
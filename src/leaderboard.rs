@@ -0,0 +1,188 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::storage;
+
+// Current version of the leaderboard file format
+const VERSION: u32 = 1;
+
+/// One finished run on the leaderboard
+#[derive(Clone)]
+pub struct Entry {
+    pub name: String,
+    pub score: i32,
+    pub date: String,  // Day the run was played, YYYY-MM-DD
+    pub duration: u64, // Length of the run in seconds
+    pub seed: u64,
+    pub mode: String,
+}
+
+/// The best runs for each game mode
+pub struct Leaderboard {
+    size: usize, // Number of entries kept per mode
+    entries: Vec<Entry>,
+}
+impl Leaderboard {
+    /// Returns a new empty leaderboard
+    ///
+    /// # Arguments
+    /// * size: the number of entries kept for each mode
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the entries for a mode, best first
+    ///
+    /// # Arguments
+    /// * mode: the game mode to list
+    pub fn top<'a>(&'a self, mode: &'a str) -> impl Iterator<Item = &'a Entry> + 'a {
+        self.entries.iter().filter(move |e| e.mode == mode)
+    }
+
+    /// Returns the best score for a mode, or 0 if it has no entries
+    ///
+    /// # Arguments
+    /// * mode: the game mode to check
+    pub fn best(&self, mode: &str) -> i32 {
+        self.top(mode).next().map_or(0, |e| e.score)
+    }
+
+    /// Returns true if a score would make it onto the leaderboard
+    ///
+    /// # Arguments
+    /// * mode: the game mode the score was made in
+    /// * score: the score to check
+    pub fn qualifies(&self, mode: &str, score: i32) -> bool {
+        score > 0 && (self.top(mode).count() < self.size || self.top(mode).any(|e| score > e.score))
+    }
+
+    /// Adds an entry, dropping the lowest entry for its mode if it is full
+    ///
+    /// Returns the entry's rank within its mode, starting at 0, or None if it
+    /// did not qualify.
+    ///
+    /// # Arguments
+    /// * entry: the entry to add
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        if !self.qualifies(&entry.mode, entry.score) {
+            return None;
+        }
+        let rank = self
+            .top(&entry.mode)
+            .filter(|e| e.score >= entry.score)
+            .count();
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.mode == entry.mode && e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        let mode = entry.mode.clone();
+        self.entries.insert(index, entry);
+
+        // Drop whatever fell off the end of the mode's table
+        if self.top(&mode).count() > self.size {
+            let last = self.entries.iter().rposition(|e| e.mode == mode).unwrap();
+            self.entries.remove(last);
+        }
+        Some(rank)
+    }
+
+    /// Writes the leaderboard to a file
+    ///
+    /// # Arguments
+    /// * path: the file to write
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut body = String::new();
+        for e in &self.entries {
+            body.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                e.mode, e.score, e.name, e.date, e.duration, e.seed
+            ));
+        }
+        storage::write_versioned(path, "leaderboard", VERSION, &body)
+    }
+
+    /// Reads a leaderboard written by `save`
+    ///
+    /// # Arguments
+    /// * path: the file to read
+    /// * size: the number of entries kept for each mode
+    pub fn load<P: AsRef<Path>>(path: P, size: usize) -> io::Result<Self> {
+        let (_, body) = storage::read_versioned(path, "leaderboard", VERSION)?
+            .ok_or_else(|| io::Error::from(ErrorKind::NotFound))?;
+        let invalid = |line: usize| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("malformed leaderboard at line {}", line + 2),
+            )
+        };
+
+        let mut leaderboard = Leaderboard::new(size);
+        for (i, line) in body.lines().enumerate() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 6 {
+                return Err(invalid(i));
+            }
+            leaderboard.insert(Entry {
+                mode: fields[0].to_string(),
+                score: fields[1].parse().map_err(|_| invalid(i))?,
+                name: fields[2].to_string(),
+                date: fields[3].to_string(),
+                duration: fields[4].parse().map_err(|_| invalid(i))?,
+                seed: fields[5].parse().map_err(|_| invalid(i))?,
+            });
+        }
+        Ok(leaderboard)
+    }
+
+    /// Reads an old `highscore.txt`, which holds a single bare score
+    ///
+    /// The score becomes the only entry, with no name, date or seed.
+    ///
+    /// # Arguments
+    /// * path: the file to read
+    /// * mode: the game mode the score was made in
+    /// * size: the number of entries kept for each mode
+    pub fn load_legacy<P: AsRef<Path>>(path: P, mode: &str, size: usize) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let score = contents
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "malformed legacy highscore"))?;
+
+        let mut leaderboard = Leaderboard::new(size);
+        leaderboard.insert(Entry {
+            name: "???".to_string(),
+            score,
+            date: "-".to_string(),
+            duration: 0,
+            seed: 0,
+            mode: mode.to_string(),
+        });
+        Ok(leaderboard)
+    }
+}
+
+/// Returns today's date (UTC) as YYYY-MM-DD
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    // Convert days since 1970-01-01 to a civil date
+    let z = (secs / 86400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -1,4 +1,18 @@
+pub mod data;
+pub mod input;
+pub mod leaderboard;
+pub mod path;
+pub mod pattern;
+pub mod replay;
+pub mod rng;
+pub mod storage;
+pub mod widget;
+
+use crossterm::style::{style, Attribute};
+use std::ops::{Add, AddAssign, Mul, Sub};
+
 /// Game map represented by a 2d vector
+#[derive(Clone)]
 pub struct Map {
     width: usize,
     height: usize,
@@ -30,12 +44,61 @@ impl Map {
         }
     }
 
+    /// Empties everything inside the walls
+    pub fn clear(&mut self) {
+        for row in self.grid[1..self.height - 1].iter_mut() {
+            for c in row[1..self.width - 1].iter_mut() {
+                *c = ' ';
+            }
+        }
+    }
+
+    /// Returns the width and height of the map
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
     /// Print the map in terminal
     pub fn display(&self) {
+        self.display_layers(&[]);
+    }
+
+    /// Print the map in terminal with extra layers drawn underneath it
+    ///
+    /// A layer only shows through in empty spaces, so anything on the map
+    /// is drawn over it. Earlier layers are drawn over later ones.
+    ///
+    /// # Arguments
+    /// * under: the layers to draw under the map
+    pub fn display_layers(&self, under: &[Layer]) {
+        self.display_all(under, &[]);
+    }
+
+    /// Print the map in terminal with extra layers drawn under and over it
+    ///
+    /// Layers over the map cover whatever is on it, e.g. to restyle part of
+    /// it. Earlier layers are drawn over later ones.
+    ///
+    /// # Arguments
+    /// * under: the layers to draw under the map
+    /// * over: the layers to draw over the map
+    pub fn display_all(&self, under: &[Layer], over: &[Layer]) {
         // Prints the map to stdout
-        for row in &self.grid {
-            for c in row {
-                print!("{}", c);
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let above = over
+                    .iter()
+                    .find_map(|l| l.get((x, y)).map(|a| (a, l.attribute)));
+                let below = under
+                    .iter()
+                    .find_map(|l| l.get((x, y)).map(|b| (b, l.attribute)));
+                match (above, below) {
+                    (Some((a, attribute)), _) => print!("{}", style(a).attribute(attribute)),
+                    (None, Some((b, attribute))) if *c == ' ' => {
+                        print!("{}", style(b).attribute(attribute))
+                    }
+                    _ => print!("{}", c),
+                }
             }
             print!("\r\n");
         }
@@ -71,11 +134,206 @@ impl Map {
         self.set((x + 1, y), c);
         self.set((x + 1, y + 1), c);
     }
+
+    /// Returns the space inside the walls at signed coordinates, or None if
+    /// they are on a wall or off the map
+    ///
+    /// # Arguments
+    /// * (x, y): coordinates to check
+    pub fn inside(&self, (x, y): (i32, i32)) -> Option<(usize, usize)> {
+        if x >= 1 && y >= 1 && (x as usize) < self.width - 1 && (y as usize) < self.height - 1 {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Draws a sprite on the map, skipping its transparent spaces
+    ///
+    /// Any part of the sprite outside the walls is not drawn, so sprites can
+    /// move on and off the map.
+    ///
+    /// # Arguments
+    /// * (x, y): the upper-left corner of the sprite
+    /// * sprite: the sprite to draw
+    pub fn draw_sprite(&mut self, (x, y): (i32, i32), sprite: &Sprite) {
+        for (dy, row) in sprite.rows.iter().enumerate() {
+            for (dx, c) in row.iter().enumerate() {
+                if let Some(pos) = self.inside((x + dx as i32, y + dy as i32)) {
+                    if *c != ' ' {
+                        self.set(pos, *c);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Casts a ray across the map one cell at a time, until something blocks
+    /// it or it reaches a wall
+    ///
+    /// Only `blocks` decides what stops the ray inside the walls, so whatever
+    /// is drawn on the map doesn't get in the way.
+    ///
+    /// # Arguments
+    /// * from: the first cell the ray passes through
+    /// * step: the columns and rows it moves each cell, e.g. `(0, -1)` for up
+    /// * blocks: returns true for a cell with something in it that stops the ray
+    pub fn raycast<F>(&self, from: (i32, i32), step: (i32, i32), blocks: F) -> Ray
+    where
+        F: Fn((i32, i32)) -> bool,
+    {
+        let mut ray = Ray {
+            cells: Vec::new(),
+            hit: None,
+        };
+        let mut cell = from;
+        while let Some(pos) = self.inside(cell) {
+            if blocks(cell) {
+                ray.hit = Some(cell);
+                break;
+            }
+            ray.cells.push(pos);
+            cell = (cell.0 + step.0, cell.1 + step.1);
+        }
+        ray
+    }
+}
+
+/// What a ray cast across the map passed through and what stopped it
+pub struct Ray {
+    pub cells: Vec<(usize, usize)>, // Cells it passed through, in order
+    pub hit: Option<(i32, i32)>,    // Where something blocked it, or None if it reached a wall
+}
+
+/// A block of characters drawn together, e.g. a large enemy
+#[derive(Clone)]
+pub struct Sprite {
+    pub rows: Vec<Vec<char>>,
+}
+impl Sprite {
+    /// Returns a sprite from its rows, top first
+    ///
+    /// Short rows are padded with spaces, which are transparent.
+    ///
+    /// # Arguments
+    /// * rows: the rows of the sprite, e.g. `["XX", "XX"]` for a 2x2 block
+    pub fn new(rows: &[&str]) -> Self {
+        let mut rows: Vec<Vec<char>> = rows.iter().map(|r| r.chars().collect()).collect();
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        for row in rows.iter_mut() {
+            row.resize(width, ' ');
+        }
+        Self { rows }
+    }
+
+    /// Returns the width and height of the sprite
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows.first().map_or(0, |r| r.len()), self.rows.len())
+    }
+}
+
+/// Extra characters drawn together with a map, all in one style
+pub struct Layer {
+    pub cells: Vec<((usize, usize), char)>,
+    pub attribute: Attribute,
+}
+impl Layer {
+    /// Returns a new empty layer
+    ///
+    /// # Arguments
+    /// * attribute: the style to draw the layer's characters with
+    pub fn new(attribute: Attribute) -> Self {
+        Self {
+            cells: Vec::new(),
+            attribute,
+        }
+    }
+
+    /// Set a space in the layer to a character
+    ///
+    /// # Arguments
+    /// * (x, y): coordinates to add
+    /// * c: the char to be added
+    pub fn set(&mut self, (x, y): (usize, usize), c: char) {
+        self.cells.retain(|cell| cell.0 != (x, y));
+        self.cells.push(((x, y), c));
+    }
+
+    /// Returns the character in a space, if the layer has one there
+    ///
+    /// # Arguments
+    /// * (x, y): coordinates to search
+    pub fn get(&self, (x, y): (usize, usize)) -> Option<char> {
+        self.cells
+            .iter()
+            .find(|cell| cell.0 == (x, y))
+            .map(|cell| cell.1)
+    }
+}
+
+/// A position or velocity on the map, precise to within a cell
+///
+/// Entities move in these and are only snapped to cells to be drawn or to
+/// check what they hit. Coordinates are signed, so they can go off the map.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+impl Vec2 {
+    /// Returns a new vector
+    ///
+    /// # Arguments
+    /// * x: the horizontal part, increasing to the right
+    /// * y: the vertical part, increasing downwards
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// Returns the cell the position is in, rounding to the nearest
+    pub fn cell(self) -> (i32, i32) {
+        ((self.x + 0.5).floor() as i32, (self.y + 0.5).floor() as i32)
+    }
+
+    /// Returns the position moved to the middle of its cell
+    pub fn snap(self) -> Self {
+        let (x, y) = self.cell();
+        Self::new(x as f64, y as f64)
+    }
+
+    /// Returns the length of the vector
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+}
+impl Add for Vec2 {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+impl Sub for Vec2 {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+impl Mul<f64> for Vec2 {
+    type Output = Self;
+    fn mul(self, scale: f64) -> Self {
+        Self::new(self.x * scale, self.y * scale)
+    }
 }
 
 /// Player entity
+#[derive(Clone)]
 pub struct Player {
-    pub pos: (usize, usize),
+    pub pos: Vec2,
     pub symbol: char,
     pub health: usize,
 }
@@ -83,12 +341,12 @@ impl Player {
     /// Returns a new player
     ///
     /// # Arguments
-    /// * (x, y): the starting position on the map
+    /// * pos: the starting position on the map
     /// * c: the char to represent the character
     /// * health: health of the player
-    pub fn new((x, y): (usize, usize), c: char, health: usize) -> Self {
+    pub fn new(pos: Vec2, c: char, health: usize) -> Self {
         Self {
-            pos: (x, y),
+            pos,
             symbol: c,
             health,
         }
@@ -96,41 +354,128 @@ impl Player {
 }
 
 /// Enemy entity
+#[derive(Clone)]
 pub struct Enemy {
-    pub pos: (usize, usize), // The upper-left coordinate if larger than 1x1
-    pub symbol: char,
+    pub pos: Vec2,            // The upper-left coordinate if larger than 1x1
+    pub velocity: Vec2,       // Cells per second
+    pub size: (usize, usize), // Width and height of the enemy's sprite
+    pub def: usize,           // Index of the enemy's type in the game's registry
+    pub health: usize,
+    pub wait: u128,   // Milliseconds since the enemy last moved
+    pub reload: u128, // Milliseconds since the enemy last fired
+    pub volleys: u64, // Number of times the enemy has fired
+    pub age: u128,    // Milliseconds since the enemy appeared
+    pub flash: u128,  // Milliseconds left to show that the enemy was hit
+
+    // State for the enemy's movement pattern
+    pub moves: u64,     // Number of moves made so far
+    pub heading: isize, // Current sideways direction, -1 or 1
+    pub anchor: Vec2,   // Point the pattern follows, e.g. the spawn point or a target
+
+    // Choreography, for enemies that fly in and wait in a formation
+    pub path: Option<usize>, // Index of the entry path being followed, if any
+    pub travelled: f64,      // Cells travelled along the path
+    pub mirrored: bool,      // Follow the path flipped left to right
+    pub slot: Option<usize>, // Formation slot held until it peels off, if any
+    pub diving: bool,        // Peeled off the formation, diving instead of its usual movement
 }
 impl Enemy {
     /// Returns a new enemy
     ///
     /// # Arguments
-    /// * (x, y): the starting position on the map
-    /// * c: the char to represent the enemy
-    pub fn new((x, y): (usize, usize), symbol: char) -> Self {
+    /// * pos: the starting position on the map
+    /// * size: the width and height of the enemy
+    /// * def: the index of the enemy's type
+    /// * health: hits the enemy can take
+    pub fn new(pos: Vec2, size: (usize, usize), def: usize, health: usize) -> Self {
         Self {
-            pos: (x, y),
-            symbol,
+            pos,
+            velocity: Vec2::default(),
+            size,
+            def,
+            health,
+            wait: 0,
+            reload: 0,
+            volleys: 0,
+            age: 0,
+            flash: 0,
+            moves: 0,
+            heading: 1,
+            anchor: pos,
+            path: None,
+            travelled: 0.0,
+            mirrored: false,
+            slot: None,
+            diving: false,
         }
     }
+
+    /// Returns true if the enemy covers a cell
+    ///
+    /// # Arguments
+    /// * (x, y): coordinates to check
+    pub fn covers(&self, (x, y): (i32, i32)) -> bool {
+        let (left, top) = self.pos.cell();
+        (left..left + self.size.0 as i32).contains(&x)
+            && (top..top + self.size.1 as i32).contains(&y)
+    }
+}
+
+/// Side an entity fights on, so bullets only hurt the other side
+#[derive(Clone, Copy, PartialEq)]
+pub enum Team {
+    Player,
+    Enemy,
 }
 
 /// Bullet entity
+#[derive(Clone)]
 pub struct Bullet {
-    pub pos: (usize, usize),
+    pub pos: Vec2,
     pub symbol: char,
+    pub team: Team,     // Side that fired the bullet
+    pub velocity: Vec2, // Cells per second
+    pub delay: u128,    // Milliseconds left before the bullet starts moving
+    pub damage: usize,  // Health taken from whatever it hits
+    pub pierce: usize,  // Enemies it can pass through after destroying them
+    pub grazed: bool,   // Whether it has already passed close by the player
 }
 impl Bullet {
     /// Returns a new bullet
     ///
     /// # Arguments
-    /// * (x, y): the starting position on the map
+    /// * pos: the starting position on the map
     /// * c: the char to represent the bullet
-    pub fn new((x, y): (usize, usize), c: char) -> Self {
+    /// * team: the side that fired the bullet
+    /// * velocity: cells per second
+    pub fn new(pos: Vec2, c: char, team: Team, velocity: Vec2) -> Self {
         Self {
-            pos: (x, y),
+            pos,
             symbol: c,
+            team,
+            velocity,
+            delay: 0,
+            damage: 1,
+            pierce: 0,
+            grazed: false,
         }
     }
+
+    /// Returns the points the bullet passes through in the next few
+    /// milliseconds, ending where it will be
+    ///
+    /// Points are never more than a cell apart, so a fast bullet cannot skip
+    /// over anything.
+    ///
+    /// # Arguments
+    /// * ms: milliseconds to move for
+    pub fn path(&self, ms: u128) -> Vec<Vec2> {
+        let delta = self.velocity * (ms as f64 / 1000.0);
+        let steps = delta.x.abs().max(delta.y.abs()).ceil().max(1.0) as usize;
+        (1..=steps)
+            .map(|step| self.pos + delta * (step as f64 / steps as f64))
+            .collect()
+    }
 }
 
 /// To keep track of the current game mode
@@ -139,28 +484,22 @@ pub enum GameMode {
     Pause,    // Pause screen
     Title,    // Title screen
     GameOver, // Game over screen
+    Replay,   // Replay viewer
+    Scores,   // Highscores screen
+    Slots,    // Save slot picker
 }
 
-/// Checks if the player has hit a 2x2 enemy
-/// If they have, lower the player's health, remove the enemy from the map,
-/// and return the enemy's position (its upper-left coordinates)
-/// Otherwise return (0,0)
+/// Checks if the player has hit an enemy
+/// If they have, lower the player's health (stopping at 0) and return true
 ///
 /// # Arguments
 ///
 /// * p: the player to check
 /// * e: the enemy to check
-/// * map: the current map
-pub fn hit_enemy(p: &mut Player, e: &Enemy, map: &mut Map) -> (usize, usize) {
-    if (e.pos.1 == p.pos.1 || e.pos.1 + 1 == p.pos.1)
-        && (e.pos.0 == p.pos.0 || e.pos.0 + 1 == p.pos.0)
-    {
-        p.health -= 1;
-        map.set(e.pos, ' ');
-        map.set((e.pos.0 + 1, e.pos.1), ' ');
-        map.set((e.pos.0, e.pos.1 + 1), ' ');
-        map.set((e.pos.0 + 1, e.pos.1 + 1), ' ');
-        return e.pos;
-    }
-    (0, 0)
+pub fn hit_enemy(p: &mut Player, e: &Enemy) -> bool {
+    if e.covers(p.pos.cell()) {
+        p.health = p.health.saturating_sub(1);
+        return true;
+    }
+    false
 }
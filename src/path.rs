@@ -0,0 +1,167 @@
+use crate::Vec2;
+
+// Points sampled along each segment of a curve before snapping to cells
+const SAMPLES: usize = 32;
+
+/// Returns -1, 0 or 1 for the sign of a distance
+///
+/// Unlike `f64::signum`, 0 gives 0, so straight gaps are filled straight.
+///
+/// # Arguments
+/// * d: the distance
+fn step(d: f64) -> f64 {
+    if d == 0.0 {
+        0.0
+    } else {
+        d.signum()
+    }
+}
+
+/// A curve through the map, sampled to the cells it passes through
+///
+/// Entities follow a path by distance, gliding from one cell to the next, so
+/// the same path can be flown at any speed.
+#[derive(Clone)]
+pub struct Path {
+    pub cells: Vec<Vec2>, // Whole cells in order, each next to the one before
+}
+impl Path {
+    /// Returns a Catmull-Rom spline, which passes through every point
+    ///
+    /// # Arguments
+    /// * points: the points to pass through, at least 2
+    pub fn catmull_rom(points: &[Vec2]) -> Self {
+        let mut samples = Vec::new();
+        for i in 0..points.len().saturating_sub(1) {
+            // The ends are repeated so the curve reaches them
+            let p0 = points[i.saturating_sub(1)];
+            let (p1, p2) = (points[i], points[i + 1]);
+            let p3 = points[(i + 2).min(points.len() - 1)];
+            for step in 0..=SAMPLES {
+                let t = step as f64 / SAMPLES as f64;
+                let (t2, t3) = (t * t, t * t * t);
+                samples.push(
+                    (p1 * 2.0
+                        + (p2 - p0) * t
+                        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+                        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+                        * 0.5,
+                );
+            }
+        }
+        Self::from_samples(&samples)
+    }
+
+    /// Returns a chain of cubic Bezier curves
+    ///
+    /// Each curve goes from an end point past two control points to the next
+    /// end point, which starts the next curve.
+    ///
+    /// # Arguments
+    /// * points: the end and control points, 3 for each curve plus 1
+    pub fn bezier(points: &[Vec2]) -> Self {
+        let mut samples = Vec::new();
+        for curve in points.windows(4).step_by(3) {
+            for step in 0..=SAMPLES {
+                let t = step as f64 / SAMPLES as f64;
+                let u = 1.0 - t;
+                samples.push(
+                    curve[0] * (u * u * u)
+                        + curve[1] * (3.0 * u * u * t)
+                        + curve[2] * (3.0 * u * t * t)
+                        + curve[3] * (t * t * t),
+                );
+            }
+        }
+        Self::from_samples(&samples)
+    }
+
+    /// Returns a path through the cells of points along a curve
+    ///
+    /// Gaps between cells are filled in so every cell is next to the one
+    /// before it, and cells visited twice in a row are dropped.
+    ///
+    /// # Arguments
+    /// * samples: points along the curve, in order
+    fn from_samples(samples: &[Vec2]) -> Self {
+        let mut cells: Vec<Vec2> = Vec::new();
+        for sample in samples {
+            let cell = sample.snap();
+            while let Some(&last) = cells.last() {
+                let d = cell - last;
+                if d.x.abs() <= 1.0 && d.y.abs() <= 1.0 {
+                    break;
+                }
+                cells.push(last + Vec2::new(step(d.x), step(d.y)));
+            }
+            if cells.last() != Some(&cell) {
+                cells.push(cell);
+            }
+        }
+        Self { cells }
+    }
+
+    /// Returns the point a distance along the path, or None past its end
+    ///
+    /// # Arguments
+    /// * distance: cells travelled from the start of the path
+    pub fn at(&self, distance: f64) -> Option<Vec2> {
+        let mut left = distance.max(0.0);
+        for pair in self.cells.windows(2) {
+            let step = (pair[1] - pair[0]).length();
+            if left < step {
+                return Some(pair[0] + (pair[1] - pair[0]) * (left / step));
+            }
+            left -= step;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Returns true if every cell is next to the one before it
+    fn connected(path: &Path) -> bool {
+        path.cells.windows(2).all(|pair| {
+            let d = pair[1] - pair[0];
+            d != Vec2::default() && d.x.abs() <= 1.0 && d.y.abs() <= 1.0
+        })
+    }
+
+    #[test]
+    fn straight_gaps_fill_straight() {
+        let path = Path::from_samples(&[Vec2::new(3.0, 2.0), Vec2::new(3.0, 7.0)]);
+        assert!(connected(&path));
+        assert_eq!(path.cells.len(), 6);
+        assert!(path.cells.iter().all(|c| c.x == 3.0));
+
+        let path = Path::from_samples(&[Vec2::new(1.0, 4.0), Vec2::new(6.0, 4.0)]);
+        assert!(connected(&path));
+        assert!(path.cells.iter().all(|c| c.y == 4.0));
+        // Each step is one cell long, so distance along it is exact
+        assert!(path.at(2.5) == Some(Vec2::new(3.5, 4.0)));
+    }
+
+    #[test]
+    fn sparse_curves_are_connected() {
+        let points = [
+            Vec2::new(-3.0, 0.0),
+            Vec2::new(20.0, 12.0),
+            Vec2::new(2.0, 25.0),
+        ];
+        let path = Path::catmull_rom(&points);
+        assert!(connected(&path));
+        assert!(path.cells.first() == Some(&Vec2::new(-3.0, 0.0)));
+        assert!(path.cells.last() == Some(&Vec2::new(2.0, 25.0)));
+
+        let path = Path::bezier(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 30.0),
+            Vec2::new(30.0, 30.0),
+            Vec2::new(30.0, 0.0),
+        ]);
+        assert!(connected(&path));
+    }
+}
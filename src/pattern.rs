@@ -0,0 +1,381 @@
+use crate::data::{DataError, Section};
+use crate::Vec2;
+
+/// The basic arrangement of bullets in one volley of a pattern
+#[derive(Clone)]
+pub enum Shape {
+    Single,             // One bullet
+    Radial(usize),      // Bullets evenly around a full circle
+    Fan(usize, f64),    // Bullets evenly across an arc, in degrees
+    Wall(usize, usize), // A line of bullets side by side, with a gap of missing bullets
+}
+
+/// One bullet fired by a pattern
+pub struct Shot {
+    pub offset: Vec2,   // Where the bullet starts, relative to the muzzle
+    pub velocity: Vec2, // Cells per second
+    pub delay: u128,    // Milliseconds the bullet waits before moving
+}
+
+/// A bullet pattern, fired one volley at a time
+///
+/// Angles are in degrees, with 0 straight down and 90 to the right. A
+/// pattern's volleys are numbered from 0, and patterns that change over time
+/// (spinning, or a wall's moving gap) use the volley number to do so.
+#[derive(Clone)]
+pub struct Pattern {
+    pub shape: Shape,
+    pub speed: f64,         // Cells per second
+    pub aimed: bool,        // Point the pattern at the target
+    pub turn: f64,          // Fixed rotation of the pattern
+    pub spin: f64,          // Rotation added with every volley
+    pub delay: u128,        // Milliseconds bullets wait before moving
+    pub with: Vec<Pattern>, // Other patterns fired in the same volley
+}
+impl Pattern {
+    /// Returns a pattern that fires a single bullet straight down
+    ///
+    /// # Arguments
+    /// * speed: cells per second
+    pub fn new(speed: f64) -> Self {
+        Self {
+            shape: Shape::Single,
+            speed,
+            aimed: false,
+            turn: 0.0,
+            spin: 0.0,
+            delay: 0,
+            with: Vec::new(),
+        }
+    }
+
+    /// Returns the bullets in one volley of the pattern
+    ///
+    /// # Arguments
+    /// * volley: the number of the volley, starting at 0
+    /// * from: where the pattern is fired from
+    /// * target: what aimed patterns point at
+    pub fn volley(&self, volley: u64, from: Vec2, target: Vec2) -> Vec<Shot> {
+        let mut angle = self.turn + self.spin * volley as f64;
+        if self.aimed {
+            angle += angle_to(from, target);
+        }
+
+        let angles: Vec<(f64, f64)> = match self.shape {
+            Shape::Single => vec![(angle, 0.0)],
+            Shape::Radial(count) => (0..count)
+                .map(|i| (angle + 360.0 * i as f64 / count as f64, 0.0))
+                .collect(),
+            Shape::Fan(count, arc) if count > 1 => (0..count)
+                .map(|i| (angle - arc / 2.0 + arc * i as f64 / (count - 1) as f64, 0.0))
+                .collect(),
+            Shape::Fan(_, _) => vec![(angle, 0.0)],
+            Shape::Wall(count, gap) => {
+                // The gap sweeps across the wall one bullet per volley
+                let gap_at = (volley as usize) % (count.saturating_sub(gap) + 1);
+                (0..count)
+                    .filter(|i| !(gap_at..gap_at + gap).contains(i))
+                    .map(|i| (angle, i as f64 - (count - 1) as f64 / 2.0))
+                    .collect()
+            }
+        };
+
+        let mut shots: Vec<Shot> = angles
+            .into_iter()
+            .map(|(angle, side)| {
+                let heading = direction(angle);
+                Shot {
+                    // Walls spread out across the direction of travel
+                    offset: Vec2::new(heading.y, -heading.x) * side,
+                    velocity: heading * self.speed,
+                    delay: self.delay,
+                }
+            })
+            .collect();
+        for pattern in &self.with {
+            shots.extend(pattern.volley(volley, from, target));
+        }
+        shots
+    }
+
+    /// Reads a pattern from its section of a data file
+    ///
+    /// Patterns look like:
+    /// ```text
+    /// [pattern spiral]
+    /// shape = radial 4
+    /// speed = 6
+    /// spin = 15
+    /// ```
+    ///
+    /// `shape` is one of `single`, `radial COUNT`, `fan COUNT ARC` or
+    /// `wall COUNT GAP`. The other fields are `speed`, `aimed` (yes or no),
+    /// `turn`, `spin`, `delay` and `with`, a comma separated list of patterns
+    /// in `library` to fire at the same time.
+    ///
+    /// # Arguments
+    /// * section: the section describing the pattern
+    /// * library: the patterns `with` can refer to
+    pub fn read(section: &Section, library: &Library) -> Result<Self, DataError> {
+        section.check_keys(&["shape", "speed", "aimed", "turn", "spin", "delay", "with"])?;
+        let line = |key: &str| {
+            section
+                .fields
+                .iter()
+                .find(|f| f.key == key)
+                .map_or(section.line, |f| f.line)
+        };
+
+        let shape = section.get("shape").unwrap_or("single");
+        let parts: Vec<&str> = shape.split_whitespace().collect();
+        let num = |i: usize| {
+            parts
+                .get(i)
+                .and_then(|p| p.parse::<usize>().ok())
+                .filter(|n| *n > 0)
+        };
+        let invalid = || section.error(line("shape"), &format!("invalid shape {}", shape));
+        let shape = match parts.first().copied() {
+            Some("single") if parts.len() == 1 => Shape::Single,
+            Some("radial") if parts.len() == 2 => Shape::Radial(num(1).ok_or_else(invalid)?),
+            Some("fan") if parts.len() == 3 => Shape::Fan(
+                num(1).ok_or_else(invalid)?,
+                parts[2].parse().map_err(|_| invalid())?,
+            ),
+            Some("wall") if parts.len() == 3 => Shape::Wall(
+                num(1).ok_or_else(invalid)?,
+                parts[2].parse().map_err(|_| invalid())?,
+            ),
+            _ => return Err(invalid()),
+        };
+
+        let aimed = match section.get("aimed").unwrap_or("no") {
+            "yes" => true,
+            "no" => false,
+            other => {
+                return Err(section.error(
+                    line("aimed"),
+                    &format!("aimed must be yes or no, not {}", other),
+                ))
+            }
+        };
+
+        let mut with = Vec::new();
+        if let Some(names) = section.get("with") {
+            for name in names.split(',').map(|n| n.trim()) {
+                let pattern = library.get(name).ok_or_else(|| {
+                    section.error(
+                        line("with"),
+                        &format!(
+                            "unknown pattern {} (patterns must be defined before use)",
+                            name
+                        ),
+                    )
+                })?;
+                with.push(pattern.clone());
+            }
+        }
+
+        Ok(Self {
+            shape,
+            speed: section.parse_or("speed", 6.0)?,
+            aimed,
+            turn: section.parse_or("turn", 0.0)?,
+            spin: section.parse_or("spin", 0.0)?,
+            delay: section.parse_or("delay", 0)?,
+            with,
+        })
+    }
+}
+
+/// Named patterns, e.g. those read from data files
+pub struct Library {
+    patterns: Vec<(String, Pattern)>,
+}
+impl Library {
+    /// Returns a new empty library
+    pub fn new() -> Self {
+        Self {
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Adds a pattern, replacing any pattern with the same name
+    ///
+    /// # Arguments
+    /// * name: the name to refer to the pattern by
+    /// * pattern: the pattern to add
+    pub fn add(&mut self, name: &str, pattern: Pattern) {
+        match self.patterns.iter_mut().find(|p| p.0 == name) {
+            Some(entry) => entry.1 = pattern,
+            None => self.patterns.push((name.to_string(), pattern)),
+        }
+    }
+
+    /// Returns a pattern by name
+    ///
+    /// # Arguments
+    /// * name: the name of the pattern
+    pub fn get(&self, name: &str) -> Option<&Pattern> {
+        self.patterns.iter().find(|p| p.0 == name).map(|p| &p.1)
+    }
+}
+impl Default for Library {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the unit vector for an angle
+///
+/// # Arguments
+/// * angle: degrees, 0 straight down and 90 to the right
+pub fn direction(angle: f64) -> Vec2 {
+    let radians = angle.to_radians();
+    Vec2::new(radians.sin(), radians.cos())
+}
+
+/// Returns the angle from one point to another
+///
+/// # Arguments
+/// * from: the starting point
+/// * to: the point to face
+pub fn angle_to(from: Vec2, to: Vec2) -> f64 {
+    let d = to - from;
+    d.x.atan2(d.y).to_degrees()
+}
+
+/// Returns the character for a bullet moving along a velocity
+///
+/// Bullets that are not moving are drawn as `*`.
+///
+/// # Arguments
+/// * velocity: the bullet's velocity
+pub fn glyph(velocity: Vec2) -> char {
+    if velocity == Vec2::default() {
+        return '*';
+    }
+    // Split the circle into eighths around each direction
+    let eighth = ((angle_to(Vec2::default(), velocity) + 22.5).rem_euclid(360.0) / 45.0) as usize;
+    match eighth % 4 {
+        0 => '|',
+        1 => '\\',
+        2 => '-',
+        _ => '/',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data;
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        (a - b).length() < 1e-9
+    }
+
+    // Reads the patterns in a data file in order, each able to use the last
+    fn read(text: &str) -> Result<Library, DataError> {
+        let mut library = Library::new();
+        for section in data::parse("test.txt", text)? {
+            let pattern = Pattern::read(&section, &library)?;
+            library.add(&section.name, pattern);
+        }
+        Ok(library)
+    }
+
+    #[test]
+    fn volley_shapes() {
+        let origin = Vec2::default();
+        let down = Vec2::new(0.0, 1.0);
+
+        let single = Pattern::new(2.0).volley(0, origin, origin);
+        assert_eq!(single.len(), 1);
+        assert!(close(single[0].velocity, down * 2.0));
+
+        let mut radial = Pattern::new(1.0);
+        radial.shape = Shape::Radial(4);
+        let shots = radial.volley(0, origin, origin);
+        assert_eq!(shots.len(), 4);
+        assert!(close(shots[1].velocity, Vec2::new(1.0, 0.0)));
+        assert!(close(shots[2].velocity, Vec2::new(0.0, -1.0)));
+
+        let mut fan = Pattern::new(1.0);
+        fan.shape = Shape::Fan(3, 180.0);
+        let shots = fan.volley(0, origin, origin);
+        assert!(close(shots[0].velocity, Vec2::new(-1.0, 0.0)));
+        assert!(close(shots[1].velocity, down));
+        assert!(close(shots[2].velocity, Vec2::new(1.0, 0.0)));
+
+        // The gap moves one bullet along each volley, then starts over
+        let mut wall = Pattern::new(1.0);
+        wall.shape = Shape::Wall(5, 2);
+        let sides = |volley| {
+            let shots = wall.volley(volley, origin, origin);
+            shots.iter().map(|s| s.offset.x).collect::<Vec<f64>>()
+        };
+        assert_eq!(sides(0), vec![0.0, 1.0, 2.0]);
+        assert_eq!(sides(1), vec![-2.0, 1.0, 2.0]);
+        assert_eq!(sides(3), vec![-2.0, -1.0, 0.0]);
+        assert_eq!(sides(4), sides(0));
+    }
+
+    #[test]
+    fn volley_aims_spins_and_adds_with() {
+        let mut aimed = Pattern::new(1.0);
+        aimed.aimed = true;
+        let shots = aimed.volley(0, Vec2::new(5.0, 5.0), Vec2::new(9.0, 5.0));
+        assert!(close(shots[0].velocity, Vec2::new(1.0, 0.0)));
+
+        let mut spinning = Pattern::new(1.0);
+        spinning.spin = 90.0;
+        spinning.with.push(Pattern::new(1.0));
+        let shots = spinning.volley(2, Vec2::default(), Vec2::default());
+        assert_eq!(shots.len(), 2);
+        assert!(close(shots[0].velocity, Vec2::new(0.0, -1.0)));
+        assert!(close(shots[1].velocity, Vec2::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn reads_patterns() {
+        let library = read(
+            "[ring]\n\
+             shape = radial 8\n\
+             speed = 4\n\
+             [spiral]\n\
+             shape = fan 3 60\n\
+             aimed = yes\n\
+             spin = 15\n\
+             delay = 200\n\
+             with = ring\n",
+        )
+        .unwrap();
+        let spiral = library.get("spiral").unwrap();
+        assert!(matches!(spiral.shape, Shape::Fan(3, arc) if arc == 60.0));
+        assert!(spiral.aimed);
+        assert_eq!((spiral.spin, spiral.delay, spiral.speed), (15.0, 200, 6.0));
+        assert_eq!(spiral.with.len(), 1);
+        assert_eq!(spiral.volley(0, Vec2::default(), Vec2::default()).len(), 11);
+    }
+
+    #[test]
+    fn rejects_bad_patterns() {
+        let error = |text: &str| read(text).err().unwrap().to_string();
+        assert_eq!(
+            error("[ring]\nspeed = 4\nshape = radial 0\n"),
+            "test.txt:3: invalid shape radial 0"
+        );
+        assert_eq!(
+            error("[ring]\nshape = fan 3\n"),
+            "test.txt:2: invalid shape fan 3"
+        );
+        assert_eq!(
+            error("[ring]\naimed = sometimes\n"),
+            "test.txt:2: aimed must be yes or no, not sometimes"
+        );
+        assert_eq!(
+            error("[ring]\nwith = spiral\n[spiral]\n"),
+            "test.txt:2: unknown pattern spiral (patterns must be defined before use)"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use crate::storage;
+
+// Current version of the replay file format
+const VERSION: u32 = 1;
+
+/// A recorded run: the seed it started from and every input, by tick
+///
+/// Inputs are stored as chars so the engine does not need to know what they
+/// mean; the game decides how to encode its commands.
+pub struct Replay {
+    pub seed: u64,
+    pub mode: String, // Game mode the run was played in, empty if not recorded
+    pub length: u64,  // Number of ticks in the run
+    pub score: i32,   // Final score of the run
+    events: Vec<(u64, char)>,
+}
+impl Replay {
+    /// Returns a new empty replay
+    ///
+    /// # Arguments
+    /// * seed: the seed the run was started with
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            mode: String::new(),
+            length: 0,
+            score: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Records an input
+    ///
+    /// # Arguments
+    /// * tick: the tick the input is applied on, no earlier than the last one
+    /// * c: the input to record
+    pub fn record(&mut self, tick: u64, c: char) {
+        self.events.push((tick, c));
+        if tick >= self.length {
+            self.length = tick + 1;
+        }
+    }
+
+    /// Marks the end of the run
+    ///
+    /// # Arguments
+    /// * length: the total number of ticks played
+    /// * score: the final score
+    pub fn finish(&mut self, length: u64, score: i32) {
+        self.length = length;
+        self.score = score;
+    }
+
+    /// Returns the inputs recorded on a tick, in the order they were made
+    ///
+    /// # Arguments
+    /// * tick: the tick to look up
+    pub fn inputs_at(&self, tick: u64) -> impl Iterator<Item = char> + '_ {
+        let start = self.events.partition_point(|e| e.0 < tick);
+        self.events[start..]
+            .iter()
+            .take_while(move |e| e.0 == tick)
+            .map(|e| e.1)
+    }
+
+    /// Returns the replay as text, one field or input per line
+    pub fn write(&self) -> String {
+        let mut body = format!(
+            "seed {}\nlength {}\nscore {}\n",
+            self.seed, self.length, self.score
+        );
+        if !self.mode.is_empty() {
+            body.push_str(&format!("mode {}\n", self.mode));
+        }
+        for (tick, c) in &self.events {
+            body.push_str(&format!("{} {}\n", tick, c));
+        }
+        body
+    }
+
+    /// Reads a replay from text returned by `write`
+    ///
+    /// # Arguments
+    /// * body: the text to read
+    pub fn read(body: &str) -> io::Result<Self> {
+        let invalid = |line: usize| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("malformed replay at line {}", line + 1),
+            )
+        };
+
+        let mut replay = Replay::new(0);
+        for (i, line) in body.lines().enumerate() {
+            let mut parts = line.splitn(2, ' ');
+            let key = parts.next().unwrap_or("");
+            let value = parts.next().ok_or_else(|| invalid(i))?;
+            match key {
+                "seed" => replay.seed = value.parse().map_err(|_| invalid(i))?,
+                "mode" => replay.mode = value.to_string(),
+                "length" => replay.length = value.parse().map_err(|_| invalid(i))?,
+                "score" => replay.score = value.parse().map_err(|_| invalid(i))?,
+                _ => {
+                    let tick = key.parse().map_err(|_| invalid(i))?;
+                    let c = value.chars().next().ok_or_else(|| invalid(i))?;
+                    // Inputs are looked up by tick, which needs them in order
+                    if replay.events.last().is_some_and(|e| e.0 > tick) {
+                        return Err(invalid(i));
+                    }
+                    replay.events.push((tick, c));
+                }
+            }
+        }
+        Ok(replay)
+    }
+
+    /// Writes the replay to a file
+    ///
+    /// # Arguments
+    /// * path: the file to write
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        storage::write_versioned(path, "replay", VERSION, &self.write())
+    }
+
+    /// Reads a replay written by `save`
+    ///
+    /// # Arguments
+    /// * path: the file to read
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let (_, body) = storage::read_versioned(path, "replay", VERSION)?
+            .ok_or_else(|| io::Error::from(ErrorKind::NotFound))?;
+        Replay::read(&body)
+    }
+}
+
+/// Periodic copies of a simulation's state
+///
+/// Lets a viewer jump to any tick by restoring the closest earlier copy and
+/// simulating forward from there instead of from tick 0.
+pub struct Snapshots<T: Clone> {
+    interval: u64,
+    states: Vec<T>,
+}
+impl<T: Clone> Snapshots<T> {
+    /// Returns an empty set of snapshots
+    ///
+    /// # Arguments
+    /// * interval: number of ticks between snapshots
+    pub fn new(interval: u64) -> Self {
+        Self {
+            interval,
+            states: Vec::new(),
+        }
+    }
+
+    /// Stores a copy of the state if a snapshot is due on this tick
+    ///
+    /// # Arguments
+    /// * tick: the tick the state is at
+    /// * state: the state to copy
+    pub fn capture(&mut self, tick: u64, state: &T) {
+        if tick == self.states.len() as u64 * self.interval {
+            self.states.push(state.clone());
+        }
+    }
+
+    /// Returns the latest snapshot at or before a tick, with the tick it was taken on
+    ///
+    /// # Arguments
+    /// * tick: the tick to seek to
+    pub fn before(&self, tick: u64) -> Option<(u64, &T)> {
+        if self.states.is_empty() {
+            return None;
+        }
+        let index = ((tick / self.interval) as usize).min(self.states.len() - 1);
+        Some((index as u64 * self.interval, &self.states[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_read_round_trips() {
+        let mut replay = Replay::new(42);
+        replay.mode = "endless".to_string();
+        replay.record(0, 'f');
+        replay.record(0, 'l');
+        replay.record(3, 'b');
+        replay.finish(10, 1234);
+
+        let read = Replay::read(&replay.write()).unwrap();
+        assert_eq!(read.write(), replay.write());
+        assert_eq!((read.seed, read.length, read.score), (42, 10, 1234));
+        assert_eq!(read.inputs_at(0).collect::<String>(), "fl");
+        assert_eq!(read.inputs_at(1).count(), 0);
+        assert_eq!(read.inputs_at(3).collect::<String>(), "b");
+    }
+
+    #[test]
+    fn read_rejects_malformed_lines() {
+        let err = Replay::read("seed 1\nlength\n").err().unwrap();
+        assert_eq!(err.to_string(), "malformed replay at line 2");
+        assert!(Replay::read("seed x\n").is_err());
+        assert!(Replay::read("seed 1\nsoon f\n").is_err());
+        let err = Replay::read("seed 1\n3 f\n3 l\n2 f\n").err().unwrap();
+        assert_eq!(err.to_string(), "malformed replay at line 4");
+    }
+
+    #[test]
+    fn snapshots_seek_to_the_closest_earlier_one() {
+        let mut snapshots = Snapshots::new(10);
+        assert!(snapshots.before(5).is_none());
+        for tick in 0..25 {
+            snapshots.capture(tick, &tick);
+        }
+        assert_eq!(snapshots.before(0), Some((0, &0)));
+        assert_eq!(snapshots.before(19), Some((10, &10)));
+        assert_eq!(snapshots.before(100), Some((20, &20)));
+    }
+}
@@ -0,0 +1,64 @@
+use rand::{Error, RngCore};
+
+/// Small seedable random number generator (xorshift64*)
+///
+/// Unlike `thread_rng`, the whole state is a single integer, so a run can be
+/// reproduced from its seed and the state can be written out and restored.
+#[derive(Clone)]
+pub struct GameRng {
+    state: u64,
+}
+impl GameRng {
+    /// Returns a new generator
+    ///
+    /// # Arguments
+    /// * seed: any value, including 0
+    pub fn new(seed: u64) -> Self {
+        // Scramble the seed (splitmix64) so nearby seeds give unrelated streams
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self::from_state(z)
+    }
+
+    /// Returns a generator that continues from a saved state
+    ///
+    /// # Arguments
+    /// * state: a value previously returned by `state`
+    pub fn from_state(state: u64) -> Self {
+        // xorshift gets stuck on 0
+        Self {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    /// Returns the current internal state
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+}
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
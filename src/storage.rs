@@ -0,0 +1,158 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, prelude::*, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Where a game keeps its save data and configuration
+///
+/// Each directory is resolved, in order, from an explicit override (e.g. a
+/// command line flag), an environment variable named after the game
+/// (`<APP>_DATA_DIR`, `<APP>_CONFIG_DIR`), the XDG base directories, and
+/// finally the user's home directory.
+pub struct Storage {
+    pub data_dir: PathBuf,
+    pub config_dir: PathBuf,
+}
+impl Storage {
+    /// Returns the storage locations for a game
+    ///
+    /// # Arguments
+    /// * app: the game's name, used for directory and variable names
+    /// * data_override: data directory to use instead of the default
+    /// * config_override: config directory to use instead of the default
+    pub fn resolve(
+        app: &str,
+        data_override: Option<PathBuf>,
+        config_override: Option<PathBuf>,
+    ) -> Self {
+        let var = |suffix: &str| env::var_os(format!("{}_{}", app.to_uppercase(), suffix));
+        let data_dir = data_override
+            .or_else(|| var("DATA_DIR").map(PathBuf::from))
+            .unwrap_or_else(|| base_dir("XDG_DATA_HOME", ".local/share").join(app));
+        let config_dir = config_override
+            .or_else(|| var("CONFIG_DIR").map(PathBuf::from))
+            .unwrap_or_else(|| base_dir("XDG_CONFIG_HOME", ".config").join(app));
+        Self {
+            data_dir,
+            config_dir,
+        }
+    }
+
+    /// Returns the path of a file in the data directory
+    ///
+    /// # Arguments
+    /// * name: the file name, may include subdirectories
+    pub fn data_path(&self, name: &str) -> PathBuf {
+        self.data_dir.join(name)
+    }
+
+    /// Returns the path of a file in the config directory
+    ///
+    /// # Arguments
+    /// * name: the file name, may include subdirectories
+    pub fn config_path(&self, name: &str) -> PathBuf {
+        self.config_dir.join(name)
+    }
+}
+
+/// Returns an XDG base directory, falling back to a folder in the home directory
+///
+/// # Arguments
+/// * var: the XDG variable to read
+/// * home_default: the default location relative to the home directory
+fn base_dir(var: &str, home_default: &str) -> PathBuf {
+    if let Some(dir) = env::var_os(var).filter(|d| Path::new(d).is_absolute()) {
+        return PathBuf::from(dir);
+    }
+    if let Some(home) = env::var_os("HOME") {
+        return PathBuf::from(home).join(home_default);
+    }
+    // Windows keeps both under the roaming app data folder
+    if let Some(appdata) = env::var_os("APPDATA") {
+        return PathBuf::from(appdata);
+    }
+    PathBuf::from(".")
+}
+
+/// Replaces a file's contents without ever leaving it half written
+///
+/// The contents go to a temporary file next to the target, which is then
+/// renamed over it. Missing parent directories are created.
+///
+/// # Arguments
+/// * path: the file to write
+/// * contents: the new contents
+pub fn write_atomic<P: AsRef<Path>>(path: P, contents: &str) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+/// Writes a versioned file: a `<kind> <version>` header line, then the body
+///
+/// # Arguments
+/// * path: the file to write
+/// * kind: what the file holds, checked when it is read back
+/// * version: the format version of the body
+/// * body: the rest of the file
+pub fn write_versioned<P: AsRef<Path>>(
+    path: P,
+    kind: &str,
+    version: u32,
+    body: &str,
+) -> io::Result<()> {
+    write_atomic(path, &format!("{} {}\n{}", kind, version, body))
+}
+
+/// Reads a file written by `write_versioned`, returning its version and body
+///
+/// Returns None if the file does not exist.
+///
+/// # Arguments
+/// * path: the file to read
+/// * kind: what the file should hold
+/// * latest: the newest version this build understands
+pub fn read_versioned<P: AsRef<Path>>(
+    path: P,
+    kind: &str,
+    latest: u32,
+) -> io::Result<Option<(u32, String)>> {
+    let path = path.as_ref();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let (header, body) = contents.split_at(contents.find('\n').map_or(contents.len(), |i| i + 1));
+
+    let mut parts = header.split_whitespace();
+    let version = match (parts.next(), parts.next().and_then(|v| v.parse().ok())) {
+        (Some(k), Some(version)) if k == kind => version,
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is not a {} file", path.display(), kind),
+            ))
+        }
+    };
+    if version > latest {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} is version {}, newer than this game supports ({})",
+                path.display(),
+                version,
+                latest
+            ),
+        ));
+    }
+    Ok(Some((version, body.to_string())))
+}
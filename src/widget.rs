@@ -0,0 +1,113 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    style::{style, Attribute},
+    QueueableCommand,
+};
+use std::io::stdout;
+
+/// Single-line text field, e.g. for entering a name
+pub struct TextInput {
+    pub text: String,
+    max_len: usize,
+}
+impl TextInput {
+    /// Returns a new empty text field
+    ///
+    /// # Arguments
+    /// * max_len: the most characters the field accepts
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            text: String::new(),
+            max_len,
+        }
+    }
+
+    /// Responds to a key press, returning true when Enter submits the text
+    ///
+    /// # Arguments
+    /// * code: the key pressed
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Enter => return !self.text.trim().is_empty(),
+            KeyCode::Backspace => {
+                self.text.pop();
+            }
+            KeyCode::Char(c)
+                if (c.is_ascii_alphanumeric() || c == ' ' || c == '-')
+                    && self.text.len() < self.max_len =>
+            {
+                self.text.push(c);
+            }
+            _ => (),
+        }
+        false
+    }
+
+    /// Print the field in terminal, padded with underscores
+    ///
+    /// # Arguments
+    /// * (x, y): the terminal position of the field
+    pub fn display(&self, (x, y): (u16, u16)) {
+        stdout().queue(cursor::MoveTo(x, y)).unwrap();
+        print!("{:_<width$}", self.text, width = self.max_len);
+    }
+}
+
+/// Vertical list of options with one selected
+pub struct Menu {
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+impl Menu {
+    /// Returns a new menu with the first item selected
+    ///
+    /// # Arguments
+    /// * items: the options, top to bottom
+    pub fn new(items: &[&str]) -> Self {
+        Self {
+            items: items.iter().map(|s| s.to_string()).collect(),
+            selected: 0,
+        }
+    }
+
+    /// Returns the selected option
+    pub fn choice(&self) -> &str {
+        &self.items[self.selected]
+    }
+
+    /// Responds to a key press, returning true when Enter picks the selected item
+    ///
+    /// # Arguments
+    /// * code: the key pressed
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Enter => return true,
+            KeyCode::Up | KeyCode::Char('w') => {
+                self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+            }
+            KeyCode::Down | KeyCode::Char('s') => {
+                self.selected = (self.selected + 1) % self.items.len();
+            }
+            _ => (),
+        }
+        false
+    }
+
+    /// Print the menu in terminal, one item per line, the selected one highlighted
+    ///
+    /// # Arguments
+    /// * (x, y): the terminal position of the first item
+    pub fn display(&self, (x, y): (u16, u16)) {
+        let width = self.items.iter().map(|s| s.len()).max().unwrap_or(0);
+        for (i, item) in self.items.iter().enumerate() {
+            stdout().queue(cursor::MoveTo(x, y + i as u16)).unwrap();
+            let line = format!("{:<width$}", item, width = width);
+            if i == self.selected {
+                print!("> {}", style(line).attribute(Attribute::Reverse));
+            } else {
+                print!("  {}", line);
+            }
+        }
+    }
+}